@@ -0,0 +1,95 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::solution::numbered_lines;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// An accepted answer: `<day> <part> <input file> <answer>` per line.
+///
+/// The answer is the rest of the line, with `\n` standing for a line break so
+/// multi-line answers such as day 10's screen fit on one line. Input files are
+/// relative to the answers file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Expected {
+    pub day: u32,
+    pub part: u32,
+    pub input: PathBuf,
+    pub answer: String,
+}
+
+pub fn load(path: &str) -> Result<Vec<Expected>> {
+    let base = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    parse(BufReader::new(File::open(path)?), base)
+}
+
+pub fn parse(input: impl BufRead, base: &Path) -> Result<Vec<Expected>> {
+    let mut expected = Vec::new();
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let trimmed = line_str.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let mut fields = Vec::new();
+        let mut rest = trimmed;
+        for _ in 0..3 {
+            let (field, tail) = rest.split_once(char::is_whitespace).ok_or_else(|| {
+                Error::malformed(
+                    line_no,
+                    &line_str,
+                    "expected `<day> <part> <input file> <answer>`",
+                )
+            })?;
+            fields.push(field);
+            rest = tail.trim_start();
+        }
+        expected.push(Expected {
+            day: error::parse_token(fields[0], line_no, &line_str)?,
+            part: error::parse_token(fields[1], line_no, &line_str)?,
+            input: base.join(fields[2]),
+            answer: rest.replace("\\n", "\n"),
+        });
+    }
+    Ok(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let answers = "# day part input answer\n\
+                       1 2 inputs/day1.txt 45000\n\
+                       \n\
+                       10 2 day10.txt ##..\\n.##.\n";
+        let expected = parse(answers.as_bytes(), Path::new("data")).unwrap();
+        assert_eq!(
+            expected,
+            vec![
+                Expected {
+                    day: 1,
+                    part: 2,
+                    input: PathBuf::from("data/inputs/day1.txt"),
+                    answer: "45000".to_string(),
+                },
+                Expected {
+                    day: 10,
+                    part: 2,
+                    input: PathBuf::from("data/day10.txt"),
+                    answer: "##..\n.##.".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_missing_answer() {
+        let err = parse("3 1 day3.txt".as_bytes(), Path::new("")).unwrap_err();
+        assert!(matches!(err, Error::MalformedLine { line: 1, .. }));
+    }
+}
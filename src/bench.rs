@@ -0,0 +1,171 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::time::Duration;
+use std::time::Instant;
+
+/// A median this much slower than the baseline is reported as a regression.
+pub const REGRESSION_RATIO: f64 = 1.1;
+
+pub struct Samples {
+    sorted: Vec<Duration>,
+}
+
+impl Samples {
+    pub fn new(mut durations: Vec<Duration>) -> Self {
+        durations.sort();
+        Samples { sorted: durations }
+    }
+
+    pub fn min(&self) -> Duration {
+        self.sorted.first().copied().unwrap_or_default()
+    }
+
+    pub fn median(&self) -> Duration {
+        let len = self.sorted.len();
+        match len {
+            0 => Duration::ZERO,
+            _ if len % 2 == 1 => self.sorted[len / 2],
+            _ => (self.sorted[len / 2 - 1] + self.sorted[len / 2]) / 2,
+        }
+    }
+
+    pub fn max(&self) -> Duration {
+        self.sorted.last().copied().unwrap_or_default()
+    }
+}
+
+pub struct Bench {
+    pub parse: Samples,
+    pub solve: Samples,
+    pub answer: Answer,
+}
+
+fn bench<S: Solution>(
+    input: &str,
+    reps: usize,
+    params: &Params,
+    part: fn(&S::Model, &Params) -> Result<Answer>,
+) -> Result<Bench> {
+    let mut parse = Vec::with_capacity(reps);
+    let mut solve = Vec::with_capacity(reps);
+    let mut answer = Answer::Number(0);
+    for _ in 0..reps {
+        let start = Instant::now();
+        let model = S::parse_str(input)?;
+        parse.push(start.elapsed());
+
+        let start = Instant::now();
+        answer = part(&model, params)?;
+        solve.push(start.elapsed());
+    }
+    Ok(Bench {
+        parse: Samples::new(parse),
+        solve: Samples::new(solve),
+        answer,
+    })
+}
+
+pub fn bench_part1<S: Solution>(input: &str, reps: usize, params: &Params) -> Result<Bench> {
+    bench::<S>(input, reps, params, S::part1)
+}
+
+pub fn bench_part2<S: Solution>(input: &str, reps: usize, params: &Params) -> Result<Bench> {
+    bench::<S>(input, reps, params, S::part2)
+}
+
+/// Median timings per (day, part), one `<day> <part> <parse ns> <solve ns>` line each.
+#[derive(Default)]
+pub struct Baseline {
+    medians: BTreeMap<(u32, u32), (Duration, Duration)>,
+}
+
+impl Baseline {
+    pub fn load(path: &str) -> Result<Self> {
+        let mut baseline = Baseline::default();
+        for line in numbered_lines(BufReader::new(File::open(path)?)) {
+            let (line_no, line_str) = line?;
+            if line_str.is_empty() || line_str.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line_str.split_whitespace().collect();
+            if words.len() != 4 {
+                return Err(Error::malformed(
+                    line_no,
+                    &line_str,
+                    "expected `<day> <part> <parse ns> <solve ns>`",
+                ));
+            }
+            let day = error::parse_token::<u32>(words[0], line_no, &line_str)?;
+            let part = error::parse_token::<u32>(words[1], line_no, &line_str)?;
+            let parse = error::parse_token::<u64>(words[2], line_no, &line_str)?;
+            let solve = error::parse_token::<u64>(words[3], line_no, &line_str)?;
+            baseline.medians.insert(
+                (day, part),
+                (Duration::from_nanos(parse), Duration::from_nanos(solve)),
+            );
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut contents = String::from("# day part parse_ns solve_ns\n");
+        for ((day, part), (parse, solve)) in &self.medians {
+            contents.push_str(&format!(
+                "{} {} {} {}\n",
+                day,
+                part,
+                parse.as_nanos(),
+                solve.as_nanos()
+            ));
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<(Duration, Duration)> {
+        self.medians.get(&(day, part)).copied()
+    }
+
+    pub fn set(&mut self, day: u32, part: u32, bench: &Bench) {
+        self.medians
+            .insert((day, part), (bench.parse.median(), bench.solve.median()));
+    }
+}
+
+pub fn is_regression(baseline: Duration, current: Duration) -> bool {
+    current.as_secs_f64() > baseline.as_secs_f64() * REGRESSION_RATIO
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples() {
+        let samples = Samples::new(
+            [4, 1, 3, 2]
+                .iter()
+                .map(|ms| Duration::from_millis(*ms))
+                .collect(),
+        );
+        assert_eq!(samples.min(), Duration::from_millis(1));
+        assert_eq!(samples.median(), Duration::from_micros(2500));
+        assert_eq!(samples.max(), Duration::from_millis(4));
+    }
+
+    #[test]
+    fn test_is_regression() {
+        let baseline = Duration::from_millis(100);
+        assert!(!is_regression(baseline, Duration::from_millis(105)));
+        assert!(is_regression(baseline, Duration::from_millis(120)));
+    }
+}
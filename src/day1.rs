@@ -1,57 +1,122 @@
-use std::cmp;
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::collections::BinaryHeap;
 use std::io;
+use std::io::BufRead;
 
-pub fn max_calories_elf() {
-    let lines = io::stdin().lines();
-
-    let mut max_cal = 0;
-    let mut cur_cal = 0;
-    for line in lines {
-        match line
-            .expect("IO failed reading data")
-            .as_str()
-            .parse::<i32>()
-        {
-            Ok(n) => {
-                cur_cal += n;
-            }
-            Err(_) => {
-                max_cal = cmp::max(max_cal, cur_cal);
-                cur_cal = 0;
-            }
+pub struct Day1;
+
+fn get_calories(input: impl BufRead) -> Result<Vec<i32>> {
+    let mut calories = Vec::new();
+    let mut cur_cal: i32 = 0;
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let trimmed = line_str.trim();
+        if trimmed.is_empty() {
+            calories.push(cur_cal);
+            cur_cal = 0;
+        } else {
+            let cal = error::parse_token::<i32>(trimmed, line_no, &line_str)?;
+            cur_cal = cur_cal.checked_add(cal).ok_or_else(|| {
+                Error::invalid_state(
+                    Some(line_no),
+                    "an elf carries more calories than fit in i32",
+                )
+            })?;
         }
     }
-    max_cal = cmp::max(max_cal, cur_cal);
-    println!("Max cal: {max_cal}");
+    calories.push(cur_cal);
+    Ok(calories)
 }
 
 const TOP_3: usize = 3;
 
-pub fn total_top_3_calories_elf() {
-    let lines = io::stdin().lines();
-
-    let mut heap = BinaryHeap::new();
-    let mut cur_cal = 0;
-    for line in lines {
-        match line
-            .expect("IO failed reading data")
-            .as_str()
-            .parse::<i32>()
-        {
-            Ok(n) => {
-                cur_cal += n;
-            }
-            Err(_) => {
-                heap.push(cur_cal);
-                heap.shrink_to(TOP_3);
-                cur_cal = 0;
-            }
-        }
+impl Solution for Day1 {
+    type Model = Vec<i32>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_calories(input)
     }
-    let mut total_cal = 0;
-    for _ in 0..TOP_3 {
-        total_cal += heap.pop().expect("Top cal");
+
+    fn part1(calories: &Self::Model, _params: &Params) -> Result<Answer> {
+        let max_cal = calories.iter().max().copied().unwrap_or(0);
+        Ok(max_cal.into())
     }
+
+    fn part2(calories: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut heap = BinaryHeap::from(calories.clone());
+        // three elves' worth may not fit in i32.
+        let mut total_cal: i64 = 0;
+        for _ in 0..TOP_3 {
+            total_cal += i64::from(
+                heap.pop()
+                    .ok_or_else(|| Error::invalid_state(None, "fewer than 3 elves"))?,
+            );
+        }
+        Ok(total_cal.into())
+    }
+}
+
+pub fn max_calories_elf() -> Result<()> {
+    let max_cal = Day1::part1(&Day1::parse(io::stdin().lock())?, &Params::default())?;
+    println!("Max cal: {max_cal}");
+    Ok(())
+}
+
+pub fn total_top_3_calories_elf() -> Result<()> {
+    let total_cal = Day1::part2(&Day1::parse(io::stdin().lock())?, &Params::default())?;
     println!("Total cal: {total_cal}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day1::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day1::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(24000)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day1::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day1::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(45000)
+        );
+    }
+
+    #[test]
+    fn test_calories_overflow() {
+        assert!(matches!(
+            Day1::parse_str("2147483647\n1\n"),
+            Err(Error::InvalidState { line: Some(2), .. })
+        ));
+    }
 }
@@ -1,29 +1,54 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::io;
+use std::io::BufRead;
 use std::vec::Vec;
+
+pub struct Day10;
+
 #[derive(Debug)]
-enum Instruction {
+pub enum Instruction {
     Addx(i32),
     Noop,
 }
-fn get_instructions() -> Vec<Instruction> {
+fn get_instructions(input: impl BufRead) -> Result<Vec<Instruction>> {
     let mut instructions = Vec::new();
+    // the register's value after each `addx`, checked so that running never overflows.
+    let mut x: i32 = 1;
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        if line_str == "noop" {
-            instructions.push(Instruction::Noop);
-        } else {
-            let mut iter = line_str.as_str().split_whitespace().clone();
-            iter.next().expect("Got addx");
-            let moves = iter
-                .next()
-                .expect("Got moves")
-                .parse::<i32>()
-                .expect("Got number of moves");
-            instructions.push(Instruction::Addx(moves));
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let words: Vec<&str> = line_str.split_whitespace().collect();
+        match words[..] {
+            ["noop"] => instructions.push(Instruction::Noop),
+            ["addx", moves] => {
+                let moves = error::parse_token::<i32>(moves, line_no, &line_str)?;
+                x = x.checked_add(moves).ok_or_else(|| {
+                    Error::invalid_state(Some(line_no), "the register overflows i32")
+                })?;
+                instructions.push(Instruction::Addx(moves));
+            }
+            [op, ..] if op != "noop" && op != "addx" => {
+                return Err(Error::unexpected_token(line_no, &line_str, op))
+            }
+            _ => {
+                return Err(Error::malformed(
+                    line_no,
+                    &line_str,
+                    "expected `noop` or `addx <value>`",
+                ))
+            }
         }
     }
-    instructions
+    if instructions.is_empty() {
+        return Err(Error::invalid_state(None, "no instructions to run"));
+    }
+    Ok(instructions)
 }
 
 const FIRST_SUM_CYCLE: i32 = 20;
@@ -37,18 +62,16 @@ fn get_instruction_duration(ins: &Instruction) -> i32 {
     }
 }
 
-pub fn sum_signal_strength() {
-    let instructions = get_instructions();
+fn get_signal_strength(instructions: &[Instruction]) -> i64 {
     let mut next_instruction = instructions.iter();
 
     let mut x = 1;
-    let mut cycle = 1;
     let mut sum_strength = 0;
     let mut current_instruction = next_instruction.next().expect("Got an instruction");
-    let mut cycles_duration = get_instruction_duration(&current_instruction);
-    for _ in 0..NUM_CYCLES {
+    let mut cycles_duration = get_instruction_duration(current_instruction);
+    for cycle in 1..=NUM_CYCLES {
         if cycle % SUM_EVERY_CYCLES == FIRST_SUM_CYCLE {
-            sum_strength += cycle * x;
+            sum_strength += i64::from(cycle) * i64::from(x);
         }
         // println!("cycles = {}: current_instruction = {:?}; x = {}", cycle, current_instruction, x);
 
@@ -59,17 +82,15 @@ pub fn sum_signal_strength() {
                 x += change
             }
             current_instruction = next_instruction.next().unwrap_or(&Instruction::Noop);
-            cycles_duration = get_instruction_duration(&current_instruction);
+            cycles_duration = get_instruction_duration(current_instruction);
         }
-        cycle += 1;
     }
-    println!("sum_strength: {}", sum_strength);
+    sum_strength
 }
 
 const NEXT_LINE_EVERY_CYCLES: i32 = 40;
 
-pub fn render_images() {
-    let instructions = get_instructions();
+fn render_screen(instructions: &[Instruction]) -> String {
     let mut next_instruction = instructions.iter();
 
     let mut screen = Vec::new();
@@ -77,14 +98,15 @@ pub fn render_images() {
     let mut cycle = 1;
     let mut current_row = Vec::new();
     let mut current_instruction = next_instruction.next().expect("Got an instruction");
-    let mut cycles_duration = get_instruction_duration(&current_instruction);
+    let mut cycles_duration = get_instruction_duration(current_instruction);
     loop {
         // println!("cycles = {}: current_instruction = {:?}; x = {}", cycle, current_instruction, x);
-        let rendered = if (x - 1..=x + 1).contains(&(current_row.len() as i32)) {
-            '#'
-        } else {
-            '.'
-        };
+        let rendered =
+            if (x.saturating_sub(1)..=x.saturating_add(1)).contains(&(current_row.len() as i32)) {
+                '#'
+            } else {
+                '.'
+            };
         current_row.push(rendered);
         if cycle % NEXT_LINE_EVERY_CYCLES == 0 {
             screen.push(current_row);
@@ -101,12 +123,228 @@ pub fn render_images() {
                 Some(ins) => ins,
                 None => break,
             };
-            cycles_duration = get_instruction_duration(&current_instruction);
+            cycles_duration = get_instruction_duration(current_instruction);
         }
         cycle += 1;
     }
+    screen
+        .iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+impl Solution for Day10 {
+    type Model = Vec<Instruction>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_instructions(input)
+    }
+
+    fn part1(instructions: &Self::Model, _params: &Params) -> Result<Answer> {
+        Ok(get_signal_strength(instructions).into())
+    }
+
+    fn part2(instructions: &Self::Model, _params: &Params) -> Result<Answer> {
+        Ok(render_screen(instructions).into())
+    }
+}
+
+pub fn sum_signal_strength() -> Result<()> {
+    let sum_strength = Day10::part1(&Day10::parse(io::stdin().lock())?, &Params::default())?;
+    println!("sum_strength: {}", sum_strength);
+    Ok(())
+}
+
+pub fn render_images() -> Result<()> {
+    let screen = Day10::part2(&Day10::parse(io::stdin().lock())?, &Params::default())?;
     println!("screen:");
-    for row in screen {
-        println!("{}", row.iter().collect::<String>());
+    println!("{}", screen);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "addx 15
+addx -11
+addx 6
+addx -3
+addx 5
+addx -1
+addx -8
+addx 13
+addx 4
+noop
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx -35
+addx 1
+addx 24
+addx -19
+addx 1
+addx 16
+addx -11
+noop
+noop
+addx 21
+addx -15
+noop
+noop
+addx -3
+addx 9
+addx 1
+addx -3
+addx 8
+addx 1
+addx 5
+noop
+noop
+noop
+noop
+noop
+addx -36
+noop
+addx 1
+addx 7
+noop
+noop
+noop
+addx 2
+addx 6
+noop
+noop
+noop
+noop
+noop
+addx 1
+noop
+noop
+addx 7
+addx 1
+noop
+addx -13
+addx 13
+addx 7
+noop
+addx 1
+addx -33
+noop
+noop
+noop
+addx 2
+noop
+noop
+noop
+addx 8
+noop
+addx -1
+addx 2
+addx 1
+noop
+addx 17
+addx -9
+addx 1
+addx 1
+addx -3
+addx 11
+noop
+noop
+addx 1
+noop
+addx 1
+noop
+noop
+addx -13
+addx -19
+addx 1
+addx 3
+addx 26
+addx -30
+addx 12
+addx -1
+addx 3
+addx 1
+noop
+noop
+noop
+addx -9
+addx 18
+addx 1
+addx 2
+noop
+noop
+addx 9
+noop
+noop
+noop
+addx -1
+addx 2
+addx -37
+addx 1
+addx 3
+noop
+addx 15
+addx -21
+addx 22
+addx -6
+addx 1
+noop
+addx 2
+addx 1
+noop
+addx -10
+noop
+noop
+addx 20
+addx 1
+addx 2
+addx 2
+addx -6
+addx -11
+noop
+noop
+noop
+";
+
+    const EXAMPLE_SCREEN: &str = "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day10::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day10::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(13140)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day10::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day10::part2(&model, &Params::default()).unwrap(),
+            Answer::from(EXAMPLE_SCREEN)
+        );
+    }
+
+    #[test]
+    fn test_register_overflow() {
+        assert!(matches!(
+            Day10::parse_str("noop\naddx 2147483647\n"),
+            Err(Error::InvalidState { line: Some(2), .. })
+        ));
     }
 }
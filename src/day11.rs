@@ -1,10 +1,20 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::cell::RefCell;
 use std::collections::BinaryHeap;
 use std::collections::VecDeque;
 use std::fmt;
 use std::io;
+use std::io::BufRead;
 use std::vec::Vec;
 
+pub struct Day11;
+
 #[derive(Debug, Clone)]
 enum Operand {
     Variable,
@@ -23,7 +33,8 @@ enum Reducer {
     Modulo(i64),
 }
 
-struct MonkeyMeta {
+#[derive(Clone)]
+pub struct MonkeyMeta {
     items: VecDeque<i64>,
     operation: Operation,
     operands: [Operand; 2],
@@ -53,7 +64,25 @@ impl fmt::Display for MonkeyMeta {
     }
 }
 
-fn get_monkey_meta() -> Vec<RefCell<MonkeyMeta>> {
+fn get_last_number(line_no: usize, line_str: &str) -> Result<i64> {
+    let last = line_str
+        .split(' ')
+        .next_back()
+        .ok_or_else(|| Error::malformed(line_no, line_str, "expected a number"))?;
+    error::parse_token::<i64>(last, line_no, line_str)
+}
+
+fn get_operand(token: &str, line_no: usize, line_str: &str) -> Result<Operand> {
+    if token == "old" {
+        Ok(Operand::Variable)
+    } else {
+        Ok(Operand::Value(error::parse_token::<i64>(
+            token, line_no, line_str,
+        )?))
+    }
+}
+
+fn get_monkey_meta(input: impl BufRead) -> Result<Vec<RefCell<MonkeyMeta>>> {
     let mut monkeys = Vec::new();
 
     let mut i = 0;
@@ -63,66 +92,63 @@ fn get_monkey_meta() -> Vec<RefCell<MonkeyMeta>> {
     let mut test_false: i64;
     let mut operation = Operation::Add;
     let mut operands: [Operand; 2] = [Operand::Variable, Operand::Variable];
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
+    let mut last_line = 0;
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        last_line = line_no;
         match i {
             1 => {
-                let splits = line_str
-                    .split(": ")
-                    .last()
-                    .expect("Got a list of items")
-                    .split(",");
-                items = VecDeque::from(
-                    splits
-                        .map(|s| s.trim().parse::<i64>().expect("a item number"))
-                        .collect::<Vec<i64>>(),
-                );
+                let (_, list) = line_str.split_once(": ").ok_or_else(|| {
+                    Error::malformed(line_no, &line_str, "expected `Starting items: ...`")
+                })?;
+                items = list
+                    .split(',')
+                    .map(|s| error::parse_token::<i64>(s.trim(), line_no, &line_str))
+                    .collect::<Result<VecDeque<i64>>>()?;
             }
             2 => {
-                let ops: Vec<&str> = line_str
-                    .split("=")
-                    .last()
-                    .expect("operation result")
-                    .trim()
-                    .split(" ")
-                    .collect();
-                // assume an `old` as an operan
-                operands = [ops[0], ops[2]].map(|s| {
-                    s.trim()
-                        .parse::<i64>()
-                        .map_or_else(|_| Operand::Variable, |v| Operand::Value(v))
-                });
+                let (_, expr) = line_str.split_once('=').ok_or_else(|| {
+                    Error::malformed(line_no, &line_str, "expected `Operation: new = ...`")
+                })?;
+                let ops: Vec<&str> = expr.split_whitespace().collect();
+                if ops.len() != 3 {
+                    return Err(Error::malformed(
+                        line_no,
+                        &line_str,
+                        "expected `<operand> <operator> <operand>`",
+                    ));
+                }
+                operands = [
+                    get_operand(ops[0], line_no, &line_str)?,
+                    get_operand(ops[2], line_no, &line_str)?,
+                ];
                 operation = match ops[1] {
                     "*" => Operation::Multiply,
                     "/" => Operation::Divide,
                     "+" => Operation::Add,
                     "-" => Operation::Subtract,
-                    _ => Operation::Add,
+                    op => return Err(Error::unexpected_token(line_no, &line_str, op)),
                 };
+                if matches!(operation, Operation::Divide)
+                    && matches!(operands[1], Operand::Value(0))
+                {
+                    return Err(Error::unexpected_token(line_no, &line_str, ops[2]));
+                }
             }
             3 => {
-                test_divisor = line_str
-                    .split(" ")
-                    .last()
-                    .expect("A divisor")
-                    .parse::<i64>()
-                    .expect("A number");
+                test_divisor = get_last_number(line_no, &line_str)?;
+                if test_divisor == 0 {
+                    return Err(Error::invalid_state(
+                        Some(line_no),
+                        "cannot test divisibility by 0",
+                    ));
+                }
             }
             4 => {
-                test_true = line_str
-                    .split(" ")
-                    .last()
-                    .expect("to a monkey")
-                    .parse::<i64>()
-                    .expect("A number");
+                test_true = get_last_number(line_no, &line_str)?;
             }
             5 => {
-                test_false = line_str
-                    .split(" ")
-                    .last()
-                    .expect("to a monkey")
-                    .parse::<i64>()
-                    .expect("A number");
+                test_false = get_last_number(line_no, &line_str)?;
                 monkeys.push(RefCell::new(MonkeyMeta {
                     items: items.clone(),
                     operation: operation.clone(),
@@ -136,39 +162,68 @@ fn get_monkey_meta() -> Vec<RefCell<MonkeyMeta>> {
         }
         i = (i + 1) % 7;
     }
+    if (1..6).contains(&i) {
+        return Err(Error::invalid_state(
+            Some(last_line),
+            "the last monkey is incomplete",
+        ));
+    }
 
-    monkeys
+    if monkeys.len() < 2 {
+        return Err(Error::invalid_state(None, "fewer than 2 monkeys"));
+    }
+    for (idx, m) in monkeys.iter().enumerate() {
+        for to in m.borrow().toss_to {
+            if to < 0 || to as usize >= monkeys.len() || to as usize == idx {
+                return Err(Error::invalid_state(
+                    None,
+                    &format!("monkey {} cannot throw to monkey {}", idx, to),
+                ));
+            }
+        }
+    }
+    Ok(monkeys)
 }
 
-fn print_monkeys(monkeys: &Vec<RefCell<MonkeyMeta>>) {
+fn print_monkeys(monkeys: &[RefCell<MonkeyMeta>]) {
     println!("monkeys: ");
     for (i, m) in monkeys.iter().enumerate() {
         println!("monkey {}: {}", i, m.borrow());
     }
 }
 
+fn apply_operation(monkey: &MonkeyMeta, item: i64) -> Result<i64> {
+    let tmp_operands = monkey
+        .operands
+        .iter()
+        .map(|o| match o {
+            Operand::Value(v) => *v,
+            Operand::Variable => item,
+        })
+        .collect::<Vec<i64>>();
+    Ok(match monkey.operation {
+        Operation::Add => tmp_operands.iter().sum(),
+        Operation::Subtract => tmp_operands[0] - tmp_operands[1],
+        Operation::Multiply => tmp_operands.iter().product(),
+        Operation::Divide => {
+            // a literal 0 is rejected when parsing, so only `old` can be 0 here.
+            if tmp_operands[1] == 0 {
+                return Err(Error::invalid_state(None, "division by a worry level of 0"));
+            }
+            tmp_operands[0] / tmp_operands[1]
+        }
+    })
+}
+
 const TEST_ROUNDS: u64 = 20;
 const MANAGED_WORRY_LEVEL: i64 = 3;
 
-fn simulate_round(monkeys: &Vec<RefCell<MonkeyMeta>>, reducer: Reducer) {
+fn simulate_round(monkeys: &[RefCell<MonkeyMeta>], reducer: Reducer) -> Result<()> {
     for monkey_cell in monkeys {
         let mut monkey = monkey_cell.borrow_mut();
         while let Some(item) = monkey.items.pop_front() {
             monkey.num_inspected += 1;
-            let tmp_operands = monkey
-                .operands
-                .iter()
-                .map(|o| match o {
-                    Operand::Value(v) => *v,
-                    Operand::Variable => item,
-                })
-                .collect::<Vec<i64>>();
-            let mut result = match monkey.operation {
-                Operation::Add => tmp_operands.iter().fold(0, |res, val| res + val),
-                Operation::Subtract => tmp_operands[0] - tmp_operands[1],
-                Operation::Multiply => tmp_operands.iter().fold(1, |res, val| res * val),
-                Operation::Divide => tmp_operands[0] / tmp_operands[1],
-            };
+            let mut result = apply_operation(&monkey, item)?;
             result = match reducer {
                 Reducer::Division(divisor) => result / divisor,
                 Reducer::Modulo(modulos) => result % modulos,
@@ -179,30 +234,25 @@ fn simulate_round(monkeys: &Vec<RefCell<MonkeyMeta>>, reducer: Reducer) {
             next_monkey.items.push_back(result);
         }
     }
+    Ok(())
 }
 
-pub fn get_two_most_active_monkey() {
-    let monkeys = get_monkey_meta();
-    print_monkeys(&monkeys);
-
-    for i in 0..TEST_ROUNDS {
-        simulate_round(&monkeys, Reducer::Division(MANAGED_WORRY_LEVEL));
-        println!("\nAfter round {}:", i + 1);
-        print_monkeys(&monkeys);
-    }
-
+fn get_monkey_business(monkeys: &[RefCell<MonkeyMeta>]) -> i64 {
     let mut heap = BinaryHeap::from(
         monkeys
             .iter()
             .map(|m| m.borrow().num_inspected)
             .collect::<Vec<i64>>(),
     );
-    let result = heap.pop().unwrap() * heap.pop().unwrap();
-    println!("{}", result)
+    heap.pop().unwrap() * heap.pop().unwrap()
 }
 
 #[allow(dead_code, unused_imports)]
-fn get_number_pattern(monkeys: &Vec<RefCell<MonkeyMeta>>, monkey_idx: usize, item: i64) -> i64 {
+fn get_number_pattern(
+    monkeys: &[RefCell<MonkeyMeta>],
+    monkey_idx: usize,
+    item: i64,
+) -> Result<i64> {
     println!("{}: {}", monkey_idx, item);
 
     let mut cur_idx = monkey_idx;
@@ -211,20 +261,7 @@ fn get_number_pattern(monkeys: &Vec<RefCell<MonkeyMeta>>, monkey_idx: usize, ite
     history.push(cur_idx);
     loop {
         let monkey = monkeys[cur_idx].borrow();
-        let tmp_operands = monkey
-            .operands
-            .iter()
-            .map(|o| match o {
-                Operand::Value(v) => *v,
-                Operand::Variable => cur_item,
-            })
-            .collect::<Vec<i64>>();
-        cur_item = match monkey.operation {
-            Operation::Add => tmp_operands.iter().fold(0, |res, val| res + val),
-            Operation::Subtract => tmp_operands[0] - tmp_operands[1],
-            Operation::Multiply => tmp_operands.iter().fold(1, |res, val| res * val),
-            Operation::Divide => tmp_operands[0] / tmp_operands[1],
-        };
+        cur_item = apply_operation(&monkey, cur_item)?;
         cur_idx = monkey.toss_to[(cur_item % monkey.test_divisor == 0) as usize] as usize;
         history.push(cur_idx);
 
@@ -237,31 +274,122 @@ fn get_number_pattern(monkeys: &Vec<RefCell<MonkeyMeta>>, monkey_idx: usize, ite
             break;
         }
     }
-    cur_item
+    Ok(cur_item)
 }
 
 const TEST_MANY_ROUNDS: u64 = 10000;
-pub fn get_two_most_active_monkey_many_rounds() {
-    let monkeys = get_monkey_meta();
-    print_monkeys(&monkeys);
 
-    let modulos = monkeys
-        .iter()
-        .map(|c| c.borrow().test_divisor)
-        .fold(1, |acc, val| acc * val);
-    for _ in 0..TEST_MANY_ROUNDS {
-        simulate_round(&monkeys, Reducer::Modulo(modulos));
-        // println!("\nAfter round {}:", i+1);
-        // print_monkeys(&monkeys);
+impl Solution for Day11 {
+    type Model = Vec<RefCell<MonkeyMeta>>;
+
+    const PARAMS: &'static [&'static str] = &["test_rounds", "test_many_rounds"];
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_monkey_meta(input)
+    }
+
+    fn part1(monkeys: &Self::Model, params: &Params) -> Result<Answer> {
+        let monkeys = monkeys.clone();
+        for _ in 0..params.get("test_rounds", TEST_ROUNDS)? {
+            simulate_round(&monkeys, Reducer::Division(MANAGED_WORRY_LEVEL))?;
+            // println!("\nAfter round {}:", i + 1);
+            // print_monkeys(&monkeys);
+        }
+        Ok(get_monkey_business(&monkeys).into())
     }
+
+    fn part2(monkeys: &Self::Model, params: &Params) -> Result<Answer> {
+        let monkeys = monkeys.clone();
+        let modulos = monkeys.iter().map(|c| c.borrow().test_divisor).product();
+        for _ in 0..params.get("test_many_rounds", TEST_MANY_ROUNDS)? {
+            simulate_round(&monkeys, Reducer::Modulo(modulos))?;
+            // println!("\nAfter round {}:", i+1);
+            // print_monkeys(&monkeys);
+        }
+        Ok(get_monkey_business(&monkeys).into())
+    }
+}
+
+pub fn get_two_most_active_monkey() -> Result<()> {
+    let monkeys = Day11::parse(io::stdin().lock())?;
     print_monkeys(&monkeys);
+    println!("{}", Day11::part1(&monkeys, &Params::default())?);
+    Ok(())
+}
 
-    let mut heap = BinaryHeap::from(
-        monkeys
-            .iter()
-            .map(|m| m.borrow().num_inspected)
-            .collect::<Vec<i64>>(),
-    );
-    let result = heap.pop().unwrap() * heap.pop().unwrap();
-    println!("{}", result)
+pub fn get_two_most_active_monkey_many_rounds() -> Result<()> {
+    let monkeys = Day11::parse(io::stdin().lock())?;
+    print_monkeys(&monkeys);
+    println!("{}", Day11::part2(&monkeys, &Params::default())?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day11::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day11::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(10605)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day11::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day11::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(2713310158)
+        );
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let literal = EXAMPLE.replacen("old * 19", "old / 0", 1);
+        assert!(matches!(
+            Day11::parse_str(&literal),
+            Err(Error::UnexpectedToken { line: 3, .. })
+        ));
+
+        let itself = EXAMPLE
+            .replacen("79, 98", "0", 1)
+            .replacen("old * 19", "old / old", 1);
+        let model = Day11::parse_str(&itself).unwrap();
+        assert!(matches!(
+            Day11::part1(&model, &Params::default()),
+            Err(Error::InvalidState { .. })
+        ));
+    }
 }
@@ -1,100 +1,126 @@
 use crate::char_bins;
+use crate::error::Error;
+use crate::error::Result;
+use crate::grid::Grid;
 use crate::matrix;
 use crate::matrix::Point;
-use std::cmp;
-use std::collections::VecDeque;
+use crate::params::Params;
+use crate::search;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::io;
-use std::vec::Vec;
-
-fn get_hillmap() -> (Vec<Vec<i32>>, Point<usize>, Point<usize>) {
-    let mut hillmap = Vec::new();
-
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let row = line_str
-            .chars()
-            .map(|c| char_bins::remap_char_to_i32(c))
-            .collect::<Vec<i32>>();
-        hillmap.push(row);
+use std::io::BufRead;
+
+pub struct Day12;
+
+pub struct HillMap {
+    heights: Grid<i32>,
+    start: Point<i64>,
+    end: Point<i64>,
+}
+
+fn get_hillmap(input: impl BufRead) -> Result<HillMap> {
+    let (heights, markers) = matrix::from_chars(input, &['S', 'E'], |c| match c {
+        'a'..='z' => Some(char_bins::remap_char_to_i32(c)),
+        'S' => Some(0),
+        'E' => Some(char_bins::remap_char_to_i32('z')),
+        _ => None,
+    })?;
+    let find = |marker: char| {
+        markers
+            .iter()
+            .rev()
+            .find(|(c, _)| *c == marker)
+            .map(|(_, point)| point.clone())
+    };
+
+    Ok(HillMap {
+        heights: Grid::new(heights),
+        start: find('S').ok_or_else(|| Error::invalid_state(None, "no start `S` on the map"))?,
+        end: find('E').ok_or_else(|| Error::invalid_state(None, "no end `E` on the map"))?,
+    })
+}
+
+fn get_min_steps(
+    hillmap: &Grid<i32>,
+    starts: impl IntoIterator<Item = Point<i64>>,
+    end: &Point<i64>,
+) -> Option<usize> {
+    let climbable = |cur: &Point<i64>| {
+        hillmap
+            .neighbors4(cur)
+            .filter(|next| hillmap[next] <= hillmap[cur] + 1)
+            .collect::<Vec<_>>()
+    };
+    search::bfs_multi(starts, climbable, |cur| cur == end).map(|(steps, _)| steps)
+}
+
+impl Solution for Day12 {
+    type Model = HillMap;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_hillmap(input)
     }
 
-    let start_val = char_bins::remap_char_to_i32('S');
-    let end_val = char_bins::remap_char_to_i32('E');
-    let mut start = Point { x: 0, y: 0 };
-    let mut end = start.clone();
-    for x in 0..hillmap.len() {
-        for y in 0..hillmap[0].len() {
-            if hillmap[x][y] == start_val {
-                start = Point { x, y };
-                hillmap[x][y] = 0;
-            } else if hillmap[x][y] == end_val {
-                end = Point { x, y };
-                hillmap[x][y] = char_bins::remap_char_to_i32('z');
-            }
-        }
+    fn part1(hill: &Self::Model, _params: &Params) -> Result<Answer> {
+        let steps = get_min_steps(&hill.heights, [hill.start.clone()], &hill.end)
+            .ok_or_else(|| Error::invalid_state(None, "the end cannot be reached"))?;
+        Ok(steps.into())
     }
-    return (hillmap, start, end);
-}
 
-fn get_min_steps(hillmap: &Vec<Vec<i32>>, start: &Point<usize>, end: &Point<usize>) -> i32 {
-    let max_x = hillmap.len();
-    let max_y = hillmap[0].len();
-    let mut visited = vec![vec![false; max_y]; max_x];
-
-    let mut steps = VecDeque::from([(start.clone(), 0)]);
-    while let Some((cur, num_steps)) = steps.pop_front() {
-        if cur == *end {
-            return num_steps;
-        }
-        for (dx, dy) in matrix::DIRECTIONS_WITH_ADJACENCY {
-            let x = cur.x as i32 + dx;
-            let y = cur.y as i32 + dy;
-            if x < 0 || x >= max_x as i32 || y < 0 || y >= max_y as i32 {
-                continue;
-            }
-            let next = Point::<usize> {
-                x: x as usize,
-                y: y as usize,
-            };
-            if hillmap[next.x][next.y] <= hillmap[cur.x][cur.y] + 1 {
-                if !visited[next.x][next.y] {
-                    visited[next.x][next.y] = true;
-                    steps.push_back((next.clone(), num_steps + 1));
-                }
-            }
-        }
+    fn part2(hill: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut hillmap = hill.heights.clone();
+        hillmap[&hill.start] = 200;
+
+        // searching from every `a` at once finds the nearest one.
+        let starts = hillmap.points().filter(|p| hillmap[p] == 0);
+        let min_result = get_min_steps(&hillmap, starts, &hill.end)
+            .ok_or_else(|| Error::invalid_state(None, "the end cannot be reached from any `a`"))?;
+        Ok(min_result.into())
     }
-    i32::MAX
 }
 
-pub fn min_steps_in_hill() {
-    let (hillmap, start, end) = get_hillmap();
-    let result = get_min_steps(&hillmap, &start, &end);
-    println!("start: {:?}", start);
-    println!("end: {:?}", end);
-    println!("steps: {:?}", result);
+pub fn min_steps_in_hill() -> Result<()> {
+    let hill = Day12::parse(io::stdin().lock())?;
+    let result = Day12::part1(&hill, &Params::default())?;
+    println!("start: {:?}", hill.start);
+    println!("end: {:?}", hill.end);
+    println!("steps: {}", result);
+    Ok(())
+}
+
+pub fn min_steps_from_a_in_hill() -> Result<()> {
+    let result = Day12::part2(&Day12::parse(io::stdin().lock())?, &Params::default())?;
+    println!("steps: {}", result);
+    Ok(())
 }
 
-// I am lazy
-pub fn min_steps_from_a_in_hill() {
-    let (mut hillmap, start, end) = get_hillmap();
-    let max_x = hillmap.len();
-    let max_y = hillmap[0].len();
-    hillmap[start.x][start.y] = 200;
-
-    let mut min_result = i32::MAX;
-    for i in 0..max_x {
-        for j in 0..max_y {
-            if hillmap[i][j] == 0 {
-                let start = Point {
-                    x: i as usize,
-                    y: j as usize,
-                };
-                min_result = cmp::min(get_min_steps(&hillmap, &start, &end), min_result);
-                // println!("start: {:?} {:?}", start, min_result);
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day12::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day12::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(31)
+        );
     }
 
-    println!("steps: {:?}", min_result);
+    #[test]
+    fn test_part2_example() {
+        let model = Day12::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day12::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(29)
+        );
+    }
 }
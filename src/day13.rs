@@ -1,14 +1,20 @@
-use crate::char_bins;
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::io;
 use std::io::BufRead;
-use std::io::BufReader;
-use std::io::Read;
 use std::vec::Vec;
 
+pub struct Day13;
+
 #[derive(PartialEq, Eq, Debug, Clone)]
-enum Conlist<T> {
+pub enum Conlist<T> {
     Head(T),
     Con(Vec<Conlist<T>>),
 }
@@ -20,93 +26,132 @@ enum Token {
     Number(i32),
 }
 
-fn get_number_from_queue(queue: &mut VecDeque<i32>) -> Option<i32> {
-    if queue.len() > 0 {
-        let mut number = 0;
-        while let Some(digit) = queue.pop_front() {
-            number = number * 10 + digit;
-        }
-        Some(number)
-    } else {
-        None
-    }
+// the number whose digits run from `start` up to `end`, if one was started.
+fn get_number(
+    line_no: usize,
+    line_str: &str,
+    start: Option<usize>,
+    end: usize,
+) -> Result<Option<i32>> {
+    start
+        .map(|start| error::parse_token::<i32>(&line_str[start..end], line_no, line_str))
+        .transpose()
+}
+
+struct TokenStream {
+    line: usize,
+    text: String,
+    tokens: VecDeque<Token>,
 }
 
-fn get_tokens_streams(input: &mut impl Read) -> Vec<VecDeque<Token>> {
+fn get_tokens_streams(input: impl BufRead) -> Result<Vec<TokenStream>> {
     let mut streams = Vec::new();
 
-    for line in BufReader::new(input).lines() {
-        let line_str = line.expect("IO failed reading data");
-        if line_str == "" {
-        } else {
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        if !line_str.is_empty() {
             let mut tokens = VecDeque::new();
-            let mut number_queue = VecDeque::new();
-            for c in line_str.chars() {
+            let mut number_start = None;
+            let mut depth = 0;
+            for (i, c) in line_str.char_indices() {
+                let unexpected =
+                    || Error::unexpected_token(line_no, &line_str, &line_str[i..i + c.len_utf8()]);
+                // everything has to sit inside the outermost list.
+                if depth == 0 && (i > 0 || c != '[') {
+                    return Err(unexpected());
+                }
                 match c {
-                    '[' => tokens.push_back(Token::ListBegin),
+                    '[' => {
+                        depth += 1;
+                        tokens.push_back(Token::ListBegin);
+                    }
                     ']' => {
-                        if let Some(number) = get_number_from_queue(&mut number_queue) {
+                        depth -= 1;
+                        if let Some(number) =
+                            get_number(line_no, &line_str, number_start.take(), i)?
+                        {
                             tokens.push_back(Token::Number(number));
                         }
                         tokens.push_back(Token::ListEnd);
                     }
                     ',' => {
-                        if let Some(number) = get_number_from_queue(&mut number_queue) {
+                        if let Some(number) =
+                            get_number(line_no, &line_str, number_start.take(), i)?
+                        {
                             tokens.push_back(Token::Number(number));
                         }
                     }
-                    _ => number_queue.push_back(char_bins::remap_char_to_i32(c)),
+                    '0'..='9' => {
+                        number_start.get_or_insert(i);
+                    }
+                    _ => return Err(unexpected()),
                 };
             }
-            streams.push(tokens);
+            if depth != 0 {
+                return Err(Error::malformed(line_no, &line_str, "unbalanced brackets"));
+            }
+            streams.push(TokenStream {
+                line: line_no,
+                text: line_str,
+                tokens,
+            });
         }
     }
-    streams
+    Ok(streams)
 }
 
-fn parse_list_contents(tokens: &mut VecDeque<Token>) -> Vec<Conlist<i32>> {
+fn parse_list_contents(tokens: &mut VecDeque<Token>) -> Option<Vec<Conlist<i32>>> {
     let mut elems = vec![];
 
-    let mut tok = tokens.pop_front().expect("list contents");
+    let mut tok = tokens.pop_front()?;
     if let Token::ListEnd = tok {
-        return elems;
+        return Some(elems);
     }
 
     loop {
-        if let Token::Number(i) = tok {
-            elems.push(Conlist::Head(i));
-        } else if let Token::ListBegin = tok {
-            elems.push(Conlist::Con(parse_list_contents(tokens)));
-        } else {
-            panic!("expected list element: {:?}", tok);
+        match tok {
+            Token::Number(i) => elems.push(Conlist::Head(i)),
+            Token::ListBegin => elems.push(Conlist::Con(parse_list_contents(tokens)?)),
+            Token::ListEnd => return None,
         }
 
-        if tokens.len() <= 0 {
+        if tokens.is_empty() {
             break;
         }
-        tok = tokens.pop_front().expect("after element");
+        tok = tokens.pop_front()?;
         if let Token::ListEnd = tok {
             break;
         }
     }
 
-    elems
+    Some(elems)
 }
 
-fn get_packets(input: &mut impl Read) -> Vec<(Conlist<i32>, Conlist<i32>)> {
+fn get_packets(input: impl BufRead) -> Result<Vec<(Conlist<i32>, Conlist<i32>)>> {
     let mut packets = Vec::new();
     let mut packet_pairs = Vec::new();
 
-    let tokens_stream = get_tokens_streams(input);
+    let tokens_stream = get_tokens_streams(input)?;
+    let mut last_line = 0;
     for mut stream in tokens_stream {
-        packet_pairs.push(parse_list_contents(&mut stream).pop().unwrap());
+        let packet = parse_list_contents(&mut stream.tokens)
+            .and_then(|mut contents| contents.pop())
+            .ok_or_else(|| Error::malformed(stream.line, &stream.text, "expected a packet"))?;
+        packet_pairs.push(packet);
+        last_line = stream.line;
 
         if packet_pairs.len() >= 2 {
             let second = packet_pairs.pop().unwrap();
             packets.push((packet_pairs.pop().unwrap(), second));
         }
     }
-    packets
+    if !packet_pairs.is_empty() {
+        return Err(Error::invalid_state(
+            Some(last_line),
+            "the last packet has no pair",
+        ));
+    }
+    Ok(packets)
 }
 
 impl<T: std::cmp::PartialEq + std::cmp::PartialOrd + Copy> PartialOrd for Conlist<T> {
@@ -122,7 +167,7 @@ impl<T: std::cmp::PartialEq + std::cmp::PartialOrd + Copy> PartialOrd for Conlis
                         Some(Ordering::Greater)
                     }
                 }
-                Conlist::Con(_) => Conlist::Con(vec![Conlist::Head(*na)]).partial_cmp(&other),
+                Conlist::Con(_) => Conlist::Con(vec![Conlist::Head(*na)]).partial_cmp(other),
             },
             Conlist::Con(con_a) => match other {
                 Conlist::Head(nb) => self.partial_cmp(&Conlist::Con(vec![Conlist::Head(*nb)])),
@@ -145,42 +190,115 @@ impl<T: std::cmp::PartialEq + std::cmp::PartialOrd + Copy> PartialOrd for Conlis
     }
 }
 
-pub fn get_distress_signal() {
-    let packets = get_packets(&mut io::stdin());
+impl Solution for Day13 {
+    type Model = Vec<(Conlist<i32>, Conlist<i32>)>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_packets(input)
+    }
 
-    let mut sum = 0;
-    for i in 0..packets.len() {
-        let index = i + 1;
-        println!("========={}========", index);
-        let success = &packets[i].0 < &packets[i].1;
-        if success {
-            sum += index;
+    fn part1(packets: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut sum = 0;
+        for (i, (left, right)) in packets.iter().enumerate() {
+            if left < right {
+                sum += i + 1;
+            }
         }
-        println!("{:?}\n\n", success);
+        Ok(sum.into())
+    }
+
+    fn part2(packets: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut flatten = Vec::new();
+        for (a, b) in packets {
+            flatten.push(a.clone());
+            flatten.push(b.clone());
+        }
+        let divider_a = Conlist::Con(vec![Conlist::Con(vec![Conlist::Head(2)])]);
+        let divider_b = Conlist::Con(vec![Conlist::Con(vec![Conlist::Head(6)])]);
+        flatten.push(divider_a.clone());
+        flatten.push(divider_b.clone());
+        flatten.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let result = (flatten
+            .binary_search_by(|probe| probe.partial_cmp(&divider_a).unwrap())
+            .unwrap()
+            + 1)
+            * (flatten
+                .binary_search_by(|probe| probe.partial_cmp(&divider_b).unwrap())
+                .unwrap()
+                + 1);
+        Ok(result.into())
     }
+}
+
+pub fn get_distress_signal() -> Result<()> {
+    let sum = Day13::part1(&Day13::parse(io::stdin().lock())?, &Params::default())?;
     println!("sum={}", sum);
+    Ok(())
 }
 
-pub fn get_distress_signal_decorder_key() {
-    let packets = get_packets(&mut io::stdin());
-    let mut flatten = Vec::new();
-    for (a, b) in packets {
-        flatten.push(a);
-        flatten.push(b);
+pub fn get_distress_signal_decorder_key() -> Result<()> {
+    let result = Day13::part2(&Day13::parse(io::stdin().lock())?, &Params::default())?;
+    println!("result={}", result);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day13::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day13::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(13)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day13::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day13::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(140)
+        );
+    }
+
+    #[test]
+    fn test_number_overflow() {
+        assert!(matches!(
+            Day13::parse_str("[99999999999]\n[1]\n"),
+            Err(Error::UnexpectedToken {
+                line: 1,
+                column: 2,
+                ..
+            })
+        ));
     }
-    let divider_a = Conlist::Con(vec![Conlist::Con(vec![Conlist::Head(2)])]);
-    let divider_b = Conlist::Con(vec![Conlist::Con(vec![Conlist::Head(6)])]);
-    flatten.push(divider_a.clone());
-    flatten.push(divider_b.clone());
-    flatten.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-    let result = (flatten
-        .binary_search_by(|probe| probe.partial_cmp(&divider_a).unwrap())
-        .unwrap()
-        + 1)
-        * (flatten
-            .binary_search_by(|probe| probe.partial_cmp(&divider_b).unwrap())
-            .unwrap()
-            + 1);
-    println!("result={:?}", result);
 }
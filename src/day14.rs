@@ -1,185 +1,193 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::grid::SparseGrid;
 use crate::matrix::Point;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::cmp;
 use std::io;
+use std::io::BufRead;
 use std::vec::Vec;
 
-const SOURCE: Point<usize> = Point::<usize> { x: 500, y: 0 };
+pub struct Day14;
+
+pub struct RockPaths {
+    paths: Vec<Vec<Vec<i32>>>,
+}
+
+const SOURCE: Point<i64> = Point::<i64> { x: 500, y: 0 };
 
 const ROCK: i32 = 1;
-const AIR: i32 = 0;
 
-fn get_paths() -> (Vec<Vec<Vec<i32>>>, i32, i32) {
+fn get_point(pair_str: &str, line_no: usize, line_str: &str) -> Result<Vec<i32>> {
+    let (x, y) = pair_str
+        .split_once(',')
+        .ok_or_else(|| Error::unexpected_token(line_no, line_str, pair_str))?;
+    Ok(vec![
+        error::parse_token::<u16>(x, line_no, line_str)? as i32,
+        error::parse_token::<u16>(y, line_no, line_str)? as i32,
+    ])
+}
+
+fn get_paths(input: impl BufRead) -> Result<RockPaths> {
     let mut paths = Vec::new();
-    let mut max_x = 0;
-    let mut max_y = 0;
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let path: Vec<Vec<i32>> = line_str
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let path = line_str
             .split(" -> ")
-            .map(|pair_str| {
-                pair_str
-                    .split(",")
-                    .map(|s| s.parse::<i32>().unwrap())
-                    .collect()
-            })
-            .collect();
-        for p in &path {
-            max_x = cmp::max(max_x, p[0]);
-            max_y = cmp::max(max_y, p[1]);
+            .map(|pair_str| get_point(pair_str, line_no, &line_str))
+            .collect::<Result<Vec<Vec<i32>>>>()?;
+        for (prev, p) in path.iter().zip(path.iter().skip(1)) {
+            if prev[0] != p[0] && prev[1] != p[1] {
+                return Err(Error::invalid_state(
+                    Some(line_no),
+                    "rock paths must be horizontal or vertical",
+                ));
+            }
         }
         paths.push(path);
     }
-    (paths, max_x, max_y)
+    Ok(RockPaths { paths })
 }
 
-// we have a transposed map for mem access purpose
-fn get_ground_map() -> Vec<Vec<i32>> {
-    let (paths, max_x, max_y) = get_paths();
-    let mut ground_map = Vec::with_capacity((max_x + 1) as usize);
-    for _ in 0..=max_x {
-        ground_map.push(vec![AIR; (max_y + 1) as usize]);
-    }
-    for path in &paths {
+fn get_ground_map(rock_paths: &RockPaths) -> SparseGrid<i32> {
+    let mut ground_map = SparseGrid::new();
+    for path in &rock_paths.paths {
         let mut prev = path.first().unwrap();
         for p in &path[1..] {
-            if prev[0] == p[0] {
+            for x in cmp::min(prev[0], p[0])..=cmp::max(prev[0], p[0]) {
                 for y in cmp::min(prev[1], p[1])..=cmp::max(prev[1], p[1]) {
-                    ground_map[p[0] as usize][y as usize] = ROCK;
-                }
-            } else {
-                for x in cmp::min(prev[0], p[0])..=cmp::max(prev[0], p[0]) {
-                    ground_map[x as usize][p[1] as usize] = ROCK;
+                    let rock = Point {
+                        x: x as i64,
+                        y: y as i64,
+                    };
+                    ground_map.insert(&rock, ROCK);
                 }
             }
             prev = p;
         }
     }
-    return ground_map;
+    ground_map
 }
 
-const OFFSET_MULTIPLIER: i32 = 4;
+// the y of the lowest rock, below which sand falls forever.
+fn get_lowest_rock(ground_map: &SparseGrid<i32>) -> i64 {
+    ground_map.bounds().map_or(0, |(_, max)| max.y)
+}
 
-fn get_actual_ground_map(offset_x: usize) -> (Vec<Vec<i32>>, Point<usize>) {
-    let (paths, mut max_x, mut max_y) = get_paths();
-    max_x += offset_x as i32 * OFFSET_MULTIPLIER;
-    max_y += 2; // new rocks here.
-    let mut offset_source = SOURCE.clone();
-    offset_source.x += offset_x;
+const DIRECTIONS: [(i64, i64); 3] = [(0, 1), (-1, 1), (1, 1)];
 
-    let mut ground_map = Vec::with_capacity((max_x + 1) as usize);
-    for _ in 0..=max_x {
-        ground_map.push(vec![AIR; (max_y + 1) as usize]);
-    }
-    for path in &paths {
-        let mut prev = path.first().unwrap();
-        for p in &path[1..] {
-            if prev[0] == p[0] {
-                for y in cmp::min(prev[1], p[1])..=cmp::max(prev[1], p[1]) {
-                    ground_map[p[0] as usize + offset_x][y as usize] = ROCK;
-                }
-            } else {
-                for x in cmp::min(prev[0], p[0])..=cmp::max(prev[0], p[0]) {
-                    ground_map[x as usize + offset_x][p[1] as usize] = ROCK;
-                }
-            }
-            prev = p;
+// where sand at `p` moves next, if anywhere; nothing rests on or below `floor`.
+fn get_next_sand(ground_map: &SparseGrid<i32>, p: &Point<i64>, floor: i64) -> Option<Point<i64>> {
+    DIRECTIONS
+        .iter()
+        .map(|(dx, dy)| Point {
+            x: p.x + dx,
+            y: p.y + dy,
+        })
+        .find(|next| next.y < floor && !ground_map.contains(next))
+}
+
+fn simluate_sanddrop(ground_map: &mut SparseGrid<i32>, lowest_rock: i64) -> bool {
+    let mut p = SOURCE.clone();
+    while let Some(next) = get_next_sand(ground_map, &p, i64::MAX) {
+        if next.y > lowest_rock {
+            // falls into the abyss
+            return false;
         }
+        p = next;
     }
-    for x in 0..=max_x {
-        ground_map[x as usize][max_y as usize] = ROCK;
+    // stable
+    ground_map.insert(&p, ROCK);
+    true
+}
+
+fn simluate_actual_sanddrop(ground_map: &mut SparseGrid<i32>, floor: i64) -> bool {
+    let mut p = SOURCE.clone();
+    while let Some(next) = get_next_sand(ground_map, &p, floor) {
+        p = next;
     }
-    return (ground_map, offset_source);
+    // stable
+    ground_map.insert(&p, ROCK);
+    p != SOURCE
 }
 
-const DIRECTIONS: [(i64, i64); 3] = [(0, 1), (-1, 1), (1, 1)];
+impl Solution for Day14 {
+    type Model = RockPaths;
 
-fn simluate_sanddrop(ground_map: &mut Vec<Vec<i32>>) -> bool {
-    let mut p = SOURCE.clone();
-    loop {
-        let mut moved = false;
-        let mut fallthrough = false;
-        for (dx, dy) in DIRECTIONS {
-            let next = Point::<usize> {
-                x: (p.x as i64 + dx) as usize,
-                y: (p.y as i64 + dy) as usize,
-            };
-            if next.x >= ground_map.len() || next.y >= ground_map[0].len() {
-                // out of bound
-                fallthrough = true;
-                continue;
-            }
-            if ground_map[next.x][next.y] != ROCK {
-                p = next;
-                moved = true;
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_paths(input)
+    }
+
+    fn part1(rock_paths: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut ground_map = get_ground_map(rock_paths);
+        let lowest_rock = get_lowest_rock(&ground_map);
+        let mut sum = 0;
+        loop {
+            let success = simluate_sanddrop(&mut ground_map, lowest_rock);
+            if success {
+                sum += 1;
+            } else {
                 break;
             }
         }
+        Ok(sum.into())
+    }
 
-        if !moved {
-            // stable
-            if fallthrough {
-                return false;
+    fn part2(rock_paths: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut ground_map = get_ground_map(rock_paths);
+        let floor = get_lowest_rock(&ground_map) + 2;
+        let mut sum = 1;
+        loop {
+            let success = simluate_actual_sanddrop(&mut ground_map, floor);
+            if success {
+                sum += 1;
+            } else {
+                break;
             }
-            ground_map[p.x][p.y] = ROCK;
-            break;
         }
+        Ok(sum.into())
     }
-    true
 }
 
-pub fn simulate_filled_sand() {
-    let mut ground_map = get_ground_map();
-    let mut sum = 0;
-    loop {
-        let success = simluate_sanddrop(&mut ground_map);
-        if success {
-            sum += 1;
-        } else {
-            break;
-        }
-    }
-    println!("sum={:?}", sum)
+pub fn simulate_filled_sand() -> Result<()> {
+    let sum = Day14::part1(&Day14::parse(io::stdin().lock())?, &Params::default())?;
+    println!("sum={}", sum);
+    Ok(())
 }
 
-fn simluate_actual_sanddrop(ground_map: &mut Vec<Vec<i32>>, source: &Point<usize>) -> bool {
-    let mut p = source.clone();
-    loop {
-        let mut moved = false;
-        for (dx, dy) in DIRECTIONS {
-            let next = Point::<usize> {
-                x: (p.x as i64 + dx) as usize,
-                y: (p.y as i64 + dy) as usize,
-            };
-            if next.x >= ground_map.len() || next.y >= ground_map[0].len() {
-                // out of bound
-                continue;
-            }
-            if ground_map[next.x][next.y] != ROCK {
-                p = next;
-                moved = true;
-                break;
-            }
-        }
+pub fn simulate_stable_sand() -> Result<()> {
+    let sum = Day14::part2(&Day14::parse(io::stdin().lock())?, &Params::default())?;
+    println!("sum={}", sum);
+    Ok(())
+}
 
-        if !moved {
-            // stable
-            ground_map[p.x][p.y] = ROCK;
-            break;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day14::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day14::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(24)
+        );
     }
-    p != (*source)
-}
 
-pub fn simulate_stable_sand() {
-    let (mut ground_map, source) = get_actual_ground_map(1000);
-    let mut sum = 1;
-    loop {
-        let success = simluate_actual_sanddrop(&mut ground_map, &source);
-        if success {
-            sum += 1;
-        } else {
-            break;
-        }
+    #[test]
+    fn test_part2_example() {
+        let model = Day14::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day14::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(93)
+        );
     }
-    println!("sum={:?}", sum)
 }
@@ -1,13 +1,24 @@
-use crate::matrix::manhattan_distance_i64;
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::interval::IntervalSet;
+use crate::matrix::checked_manhattan_distance;
+use crate::matrix::manhattan_distance;
 use crate::matrix::Point;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::cmp;
 use std::collections::HashSet;
 use std::io;
-use std::ops::Range;
+use std::io::BufRead;
 use std::vec::Vec;
 
+pub struct Day15;
+
 #[derive(Debug)]
-struct BoundedSensor {
+pub struct BoundedSensor {
     sensor: Point<i64>,
     beacon: Point<i64>,
 }
@@ -18,47 +29,59 @@ struct ManhattanSensor {
     distance: i64,
 }
 
-fn get_locations() -> (Vec<BoundedSensor>, Point<i64>) {
+const SENSOR_PREFIX: &str = "Sensor at x=";
+const BEACON_PREFIX: &str = "closest beacon is at x=";
+
+fn get_point(point_str: &str, prefix: &str, line_no: usize, line_str: &str) -> Result<Point<i64>> {
+    let expected = || Error::malformed(line_no, line_str, &format!("expected `{}X, y=Y`", prefix));
+    let (x, y) = point_str
+        .strip_prefix(prefix)
+        .and_then(|s| s.split_once(", y="))
+        .ok_or_else(expected)?;
+    Ok(Point::<i64> {
+        x: error::parse_token::<i64>(x, line_no, line_str)?,
+        y: error::parse_token::<i64>(y, line_no, line_str)?,
+    })
+}
+
+fn get_locations(input: impl BufRead) -> Result<(Vec<BoundedSensor>, Point<i64>)> {
     let mut max_x = 0;
     let mut max_y = 0;
     let mut sensors = Vec::new();
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let parts: Vec<&str> = line_str.split(": ").collect();
-        let mut sensor_str = parts[0];
-        sensor_str = &sensor_str[12..sensor_str.len()];
-        let sensor_point: Vec<i64> = sensor_str
-            .split(", y=")
-            .map(|x| x.parse::<i64>().unwrap())
-            .collect();
-        let mut beacon_str = parts[1];
-        beacon_str = &beacon_str[23..beacon_str.len()];
-        let beacon_point: Vec<i64> = beacon_str
-            .split(", y=")
-            .map(|x| x.parse::<i64>().unwrap())
-            .collect();
-        sensors.push(BoundedSensor {
-            sensor: Point::<i64> {
-                x: sensor_point[0],
-                y: sensor_point[1],
-            },
-            beacon: Point::<i64> {
-                x: beacon_point[0],
-                y: beacon_point[1],
-            },
-        });
-        max_x = cmp::max(max_x, sensor_point[0]);
-        max_x = cmp::max(max_x, beacon_point[0]);
-        max_y = cmp::max(max_y, sensor_point[1]);
-        max_y = cmp::max(max_y, beacon_point[1]);
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let (sensor_str, beacon_str) = line_str
+            .split_once(": ")
+            .ok_or_else(|| Error::malformed(line_no, &line_str, "expected `<sensor>: <beacon>`"))?;
+        let sensor = get_point(sensor_str, SENSOR_PREFIX, line_no, &line_str)?;
+        let beacon = get_point(beacon_str, BEACON_PREFIX, line_no, &line_str)?;
+        // the solver walks `distance + 1` away from the sensor along both axes.
+        let reach = checked_manhattan_distance(&sensor, &beacon)
+            .and_then(|distance| distance.checked_add(1))
+            .filter(|reach| {
+                [sensor.x, sensor.y]
+                    .iter()
+                    .all(|c| c.checked_add(*reach).is_some() && c.checked_sub(*reach).is_some())
+            });
+        if reach.is_none() {
+            return Err(Error::invalid_state(
+                Some(line_no),
+                "the sensor reaches past the positions an i64 can hold",
+            ));
+        }
+        max_x = cmp::max(max_x, sensor.x);
+        max_x = cmp::max(max_x, beacon.x);
+        max_y = cmp::max(max_y, sensor.y);
+        max_y = cmp::max(max_y, beacon.y);
+        sensors.push(BoundedSensor { sensor, beacon });
     }
-    (sensors, Point::<i64> { x: max_x, y: max_y })
+    Ok((sensors, Point::<i64> { x: max_x, y: max_y }))
 }
 
-fn get_manhattan_sensors(sensors: &Vec<BoundedSensor>) -> Vec<ManhattanSensor> {
+fn get_manhattan_sensors(sensors: &[BoundedSensor]) -> Vec<ManhattanSensor> {
     let mut manhattan = Vec::new();
     for sensor in sensors {
-        let distance = manhattan_distance_i64(&sensor.sensor, &sensor.beacon);
+        let distance = manhattan_distance(&sensor.sensor, &sensor.beacon);
         manhattan.push(ManhattanSensor {
             sensor: sensor.sensor.clone(),
             distance,
@@ -67,112 +90,148 @@ fn get_manhattan_sensors(sensors: &Vec<BoundedSensor>) -> Vec<ManhattanSensor> {
     manhattan
 }
 
-fn merge_ranges(ranges: &Vec<Range<i64>>) -> Vec<Range<i64>> {
-    let mut sorted = ranges.clone();
-    sorted.sort_by(|a, b| a.start.cmp(&b.start));
-
-    let mut res = Vec::new();
-    if sorted.len() == 0 {
-        return res;
-    }
-
-    res.push(sorted[0].clone());
-    for inteval in &sorted[1..] {
-        let top = res.first().unwrap().clone();
-        if top.end < inteval.start {
-            // no overlap
-            res.push(inteval.clone());
-        } else if top.end == inteval.start || top.end < inteval.end {
-            let new_inteval = top.start..inteval.end;
-            res.pop();
-            res.push(new_inteval);
-        }
-    }
-    res
-}
-
-const TARGET_Y: i64 = 2000000;
-
-pub fn get_num_positions_no_beacon() {
-    let (sensors, _) = get_locations();
-    let manhattan = get_manhattan_sensors(&sensors);
-    let mut ranges = Vec::new();
+// the x positions on row `target_y` within reach of any sensor.
+fn get_row_coverage(manhattan: &[ManhattanSensor], target_y: i64) -> IntervalSet<i64> {
+    let mut coverage = IntervalSet::new();
     for bounded_manhattan in manhattan {
         if ((bounded_manhattan.sensor.y - bounded_manhattan.distance)
             ..=(bounded_manhattan.sensor.y + bounded_manhattan.distance))
-            .contains(&TARGET_Y)
+            .contains(&target_y)
         {
-            // found a sensor in range of TARGET_Y
-            let used_diff_y = i64::abs(bounded_manhattan.sensor.y - TARGET_Y);
+            // found a sensor in range of target_y
+            let used_diff_y = i64::abs(bounded_manhattan.sensor.y - target_y);
             let rest_x = bounded_manhattan.distance - used_diff_y;
-            ranges.push(
+            coverage.insert(
                 (bounded_manhattan.sensor.x - rest_x)..(bounded_manhattan.sensor.x + rest_x + 1),
             );
         }
     }
-    ranges = merge_ranges(&ranges);
+    coverage
+}
+
+const TARGET_Y: i64 = 2000000;
 
-    let mut sum = 0;
-    let x_points: Vec<i64> = sensors
+fn count_positions_no_beacon(sensors: &[BoundedSensor], target_y: i64) -> i64 {
+    let coverage = get_row_coverage(&get_manhattan_sensors(sensors), target_y);
+
+    let x_point_set: HashSet<i64> = sensors
         .iter()
         .flat_map(|b| [&b.sensor, &b.beacon])
-        .filter(|p| p.y == TARGET_Y)
+        .filter(|p| p.y == target_y)
         .map(|p| p.x)
         .collect();
-    let x_point_set: HashSet<i64> = x_points.into_iter().collect();
-    for r in ranges {
-        let mut seg_sum = r.end - r.start;
-        for x_point in &x_point_set {
-            if r.contains(&x_point) {
-                seg_sum -= 1;
-            }
-        }
-        sum += seg_sum;
-    }
-    println!("sum={}", &sum);
+    let occupied = x_point_set.iter().filter(|x| coverage.contains(x)).count();
+    coverage.covered_len() - occupied as i64
 }
 
 const SEARCH_BOUND: i64 = 4000000;
 
-pub fn get_distress_beacon() {
-    let (sensors, _) = get_locations();
-    let manhattan = get_manhattan_sensors(&sensors);
-
-    for target_y in 0..=SEARCH_BOUND {
-        let mut ranges = Vec::new();
-        for bounded_manhattan in &manhattan {
-            if ((bounded_manhattan.sensor.y - bounded_manhattan.distance)
-                ..=(bounded_manhattan.sensor.y + bounded_manhattan.distance))
-                .contains(&target_y)
-            {
-                // found a sensor in range of TARGET_Y
-                let used_diff_y = i64::abs(bounded_manhattan.sensor.y - target_y);
-                let rest_x = bounded_manhattan.distance - used_diff_y;
-                ranges.push(
-                    (bounded_manhattan.sensor.x - rest_x)
-                        ..(bounded_manhattan.sensor.x + rest_x + 1),
-                );
-            }
-        }
-        ranges = merge_ranges(&ranges);
-        println!("target_y={} {:?}", target_y, ranges);
-        if ranges.len() == 1 && ranges[0].start < 0 && ranges[0].end > SEARCH_BOUND {
-            continue;
-        }
+fn find_distress_beacon(sensors: &[BoundedSensor], search_bound: i64) -> Result<Option<i64>> {
+    let manhattan = get_manhattan_sensors(sensors);
 
-        // this O(n^2) is slow but we can cut lots of branches above.
-        for target_x in 0..=SEARCH_BOUND {
-            let mut matched = false;
-            for range in &ranges {
-                if !matched && range.contains(&target_x) {
-                    matched = true;
-                }
-            }
-            if !matched {
-                println!("x={} y={}", target_x, target_y);
-                println!("score={}", 4000000 * target_x + target_y);
-                return;
-            }
+    for target_y in 0..=search_bound {
+        let coverage = get_row_coverage(&manhattan, target_y);
+        // println!("target_y={} {:?}", target_y, coverage);
+        if let Some(gap) = coverage.gaps(0..search_bound.saturating_add(1)).next() {
+            // println!("x={} y={}", gap.start, target_y);
+            return gap
+                .start
+                .checked_mul(4000000)
+                .and_then(|x| x.checked_add(target_y))
+                .map(Some)
+                .ok_or_else(|| Error::invalid_state(None, "the tuning frequency overflows i64"));
         }
     }
+    Ok(None)
+}
+
+impl Solution for Day15 {
+    type Model = Vec<BoundedSensor>;
+
+    const PARAMS: &'static [&'static str] = &["target_y", "search_bound"];
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        let (sensors, _) = get_locations(input)?;
+        Ok(sensors)
+    }
+
+    fn part1(sensors: &Self::Model, params: &Params) -> Result<Answer> {
+        Ok(count_positions_no_beacon(sensors, params.get("target_y", TARGET_Y)?).into())
+    }
+
+    fn part2(sensors: &Self::Model, params: &Params) -> Result<Answer> {
+        let score = find_distress_beacon(sensors, params.get("search_bound", SEARCH_BOUND)?)?
+            .ok_or_else(|| {
+                Error::invalid_state(None, "no position left for the distress beacon")
+            })?;
+        Ok(score.into())
+    }
+}
+
+pub fn get_num_positions_no_beacon() -> Result<()> {
+    let sum = Day15::part1(&Day15::parse(io::stdin().lock())?, &Params::default())?;
+    println!("sum={}", sum);
+    Ok(())
+}
+
+pub fn get_distress_beacon() -> Result<()> {
+    let score = Day15::part2(&Day15::parse(io::stdin().lock())?, &Params::default())?;
+    println!("score={}", score);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3
+";
+    // the example asks about row 10 and a 0..=20 search area instead.
+    fn example_params() -> Params {
+        let mut params = Params::default();
+        params.insert("target_y", "10");
+        params.insert("search_bound", "20");
+        params
+    }
+
+    #[test]
+    fn test_part1_example() {
+        let sensors = Day15::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day15::part1(&sensors, &example_params()).unwrap(),
+            Answer::Number(26)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let sensors = Day15::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day15::part2(&sensors, &example_params()).unwrap(),
+            Answer::Number(56000011)
+        );
+    }
+
+    #[test]
+    fn test_distance_overflow() {
+        let input = "Sensor at x=9223372036854775807, y=0: \
+                     closest beacon is at x=-9223372036854775807, y=0\n";
+        assert!(matches!(
+            Day15::parse_str(input),
+            Err(Error::InvalidState { line: Some(1), .. })
+        ));
+    }
 }
@@ -1,15 +1,34 @@
 // DP needs a overly large states to represent if a set of valve is opened (minimal bitset)
 // But cutting it to 15 is managable
 
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::search;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use itertools::Itertools;
 use std::cmp;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::io;
+use std::io::BufRead;
 use std::vec::Vec;
 
-fn get_valve_map() -> (Vec<String>, Vec<i64>, HashMap<usize, Vec<usize>>) {
+pub struct Day16;
+
+pub struct ValveMap {
+    encoded_valve_names: Vec<String>,
+    valve_map: Vec<i64>,
+    tunnel_map: HashMap<usize, Vec<usize>>,
+}
+
+const TUNNEL_PREFIXES: [&str; 2] = ["tunnels lead to valves ", "tunnel leads to valve "];
+
+fn get_valve_map(input: impl BufRead) -> Result<ValveMap> {
     let mut valves = HashMap::new();
     let mut tunnels = HashMap::new();
     let mut cnt = 1;
@@ -17,30 +36,37 @@ fn get_valve_map() -> (Vec<String>, Vec<i64>, HashMap<usize, Vec<usize>>) {
     encoding.insert("AA".to_string(), 0);
     let mut encoded_valve_names = vec!["AA".to_string()];
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let parts: Vec<&str> = line_str.split("; ").collect();
-        let mut valve_str = parts[0];
-        valve_str = &valve_str[6..valve_str.len()];
-        let valve_parts: Vec<&str> = valve_str.split(" has flow rate=").collect();
-        let valve_name = valve_parts[0].to_string();
-        if let None = encoding.get(&valve_name) {
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let (valve_str, tunnel_str) = line_str
+            .strip_prefix("Valve ")
+            .and_then(|s| s.split_once("; "))
+            .ok_or_else(|| {
+                Error::malformed(
+                    line_no,
+                    &line_str,
+                    "expected `Valve <name> has flow rate=<rate>; <tunnels>`",
+                )
+            })?;
+        let (valve_name, rate_str) = valve_str
+            .split_once(" has flow rate=")
+            .ok_or_else(|| Error::malformed(line_no, &line_str, "expected a flow rate"))?;
+        let valve_name = valve_name.to_string();
+        if !encoding.contains_key(&valve_name) {
             encoding.insert(valve_name.clone(), cnt);
             encoded_valve_names.push(valve_name.clone());
             cnt += 1;
         }
-        let valve_val = valve_parts[1].parse::<i64>().unwrap();
+        let valve_val = error::parse_token::<i64>(rate_str, line_no, &line_str)?;
         valves.insert(valve_name.clone(), valve_val);
 
-        let tunnel_parts: Vec<&str> = parts[1].split(", ").collect();
-        let mut other_valves = Vec::new();
-        let tunnel_part = tunnel_parts[0];
-        other_valves.push(tunnel_part[tunnel_part.len() - 2..tunnel_part.len()].to_string());
-        for tunnel_part in &tunnel_parts[1..] {
-            other_valves.push(tunnel_part.to_string());
-        }
+        let tunnel_list = TUNNEL_PREFIXES
+            .iter()
+            .find_map(|prefix| tunnel_str.strip_prefix(prefix))
+            .ok_or_else(|| Error::unexpected_token(line_no, &line_str, tunnel_str))?;
+        let other_valves: Vec<String> = tunnel_list.split(", ").map(String::from).collect();
         for valve_name in &other_valves {
-            if let None = encoding.get(valve_name) {
+            if !encoding.contains_key(valve_name) {
                 encoding.insert(valve_name.clone(), cnt);
                 encoded_valve_names.push(valve_name.clone());
                 cnt += 1;
@@ -63,33 +89,33 @@ fn get_valve_map() -> (Vec<String>, Vec<i64>, HashMap<usize, Vec<usize>>) {
         );
     }
 
-    (encoded_valve_names, encoded_valve_rate, encoded_tunnels)
+    Ok(ValveMap {
+        encoded_valve_names,
+        valve_map: encoded_valve_rate,
+        tunnel_map: encoded_tunnels,
+    })
 }
 
-// Floyd–Warshall algorithm
-// but cut the valve if rate is 0.
+fn get_start(valves: &ValveMap, start: &str) -> Result<usize> {
+    valves
+        .encoded_valve_names
+        .iter()
+        .position(|x| x == start)
+        .ok_or_else(|| Error::invalid_state(None, &format!("no valve named {}", start)))
+}
+
+// shortest number of steps between every pair of valves, by a bfs from each.
 fn get_distances_valve(
-    valve_map: &Vec<i64>,
+    valve_map: &[i64],
     tunnel_map: &HashMap<usize, Vec<usize>>,
 ) -> Vec<Vec<i64>> {
     let len_valve = valve_map.len();
     let mut dist = vec![vec![i64::MAX / 2; len_valve]; len_valve];
 
-    for (valve, other_valves) in tunnel_map {
-        // unlike the Floyd, we ignored valve with flow rate 0. except "AA"
-        // dfs here
-        for other_valve in other_valves {
-            dist[*valve][*other_valve] = 1;
-        }
-    }
-
-    for k in 0..len_valve {
-        for i in 0..len_valve {
-            for j in 0..len_valve {
-                if dist[i][j] > dist[i][k] + dist[k][j] {
-                    dist[i][j] = dist[i][k] + dist[k][j]
-                }
-            }
+    let tunnels = |valve: &usize| tunnel_map.get(valve).into_iter().flatten().copied();
+    for (i, row) in dist.iter_mut().enumerate() {
+        for (j, steps) in search::bfs_distances(i, tunnels) {
+            row[j] = steps as i64;
         }
     }
     dist
@@ -111,8 +137,9 @@ struct TransitionState {
 fn get_maxed_transit(
     start: usize,
     init_time: i64,
-    valve_map: &Vec<i64>,
-    dist: &Vec<Vec<i64>>,
+    max_minutes: i64,
+    valve_map: &[i64],
+    dist: &[Vec<i64>],
     valid: &HashSet<usize>,
 ) -> TransitionState {
     let mut maxed_transit = TransitionState {
@@ -126,13 +153,11 @@ fn get_maxed_transit(
     let mut q = VecDeque::new();
     q.push_back(maxed_transit.clone());
     while let Some(mut cur) = q.pop_front() {
-        if valve_map[cur.position] > 0 {
-            if !cur.opened.contains(&cur.position) {
-                cur.opened.insert(cur.position);
-                cur.released_pressure += cur.flow;
-                cur.flow += valve_map[cur.position];
-                cur.time += 1;
-            }
+        if valve_map[cur.position] > 0 && !cur.opened.contains(&cur.position) {
+            cur.opened.insert(cur.position);
+            cur.released_pressure += cur.flow;
+            cur.flow += valve_map[cur.position];
+            cur.time += 1;
         }
 
         let mut transit = false;
@@ -141,7 +166,7 @@ fn get_maxed_transit(
                 continue;
             }
             let cost = dist[cur.position][*next];
-            if cur.time + cost >= MAX_MINUTES {
+            if cur.time + cost >= max_minutes {
                 continue;
             }
 
@@ -155,8 +180,8 @@ fn get_maxed_transit(
         }
         if !transit {
             let mut next_state = cur.clone();
-            next_state.released_pressure += (MAX_MINUTES - next_state.time) * next_state.flow;
-            next_state.time = MAX_MINUTES;
+            next_state.released_pressure += (max_minutes - next_state.time) * next_state.flow;
+            next_state.time = max_minutes;
             if next_state.released_pressure > maxed_transit.released_pressure {
                 maxed_transit = next_state.clone();
             }
@@ -165,11 +190,12 @@ fn get_maxed_transit(
     maxed_transit
 }
 
+#[allow(dead_code, unused_imports)]
 fn print_path(
     maxed_transit: &TransitionState,
-    valve_map: &Vec<i64>,
-    encoded_valve_names: &Vec<String>,
-    dist: &Vec<Vec<i64>>,
+    valve_map: &[i64],
+    encoded_valve_names: &[String],
+    dist: &[Vec<i64>],
 ) {
     println!("{:?}", maxed_transit);
     let mut minute = 0;
@@ -189,64 +215,132 @@ fn print_path(
     }
 }
 
-pub fn get_max_flow() {
-    let (encoded_valve_names, valve_map, tunnel_map) = get_valve_map();
-    let valid: HashSet<usize> = HashSet::from_iter(
+fn get_valid_valves(valve_map: &[i64]) -> HashSet<usize> {
+    HashSet::from_iter(
         valve_map
             .iter()
             .enumerate()
             .filter(|(_, rate)| **rate > 0)
-            .map(|(idx, _)| idx)
-            .clone(),
-    );
-    let start = encoded_valve_names.iter().position(|x| x == START).unwrap();
-    let dist = get_distances_valve(&valve_map, &tunnel_map);
-
-    println!(
-        "encoded_valve_names={:?} valve_map={:?}, tunnel_map={:?}",
-        encoded_valve_names, valve_map, tunnel_map
-    );
-    // println!("dist={:?} / valid={:?}", dist, valid);
-    let maxed_transit = get_maxed_transit(start, 0, &valve_map, &dist, &valid);
-    print_path(&maxed_transit, &valve_map, &encoded_valve_names, &dist);
+            .map(|(idx, _)| idx),
+    )
 }
 
 const ELEPHANT_INIT_TIME: i64 = 4;
 
-pub fn get_max_flow_with_elephant() {
-    let (encoded_valve_names, valve_map, tunnel_map) = get_valve_map();
-    let valid: HashSet<usize> = HashSet::from_iter(
-        valve_map
-            .iter()
-            .enumerate()
-            .filter(|(_, rate)| **rate > 0)
-            .map(|(idx, _)| idx)
-            .clone(),
-    );
-    let start = encoded_valve_names.iter().position(|x| x == START).unwrap();
-    let dist = get_distances_valve(&valve_map, &tunnel_map);
+impl Solution for Day16 {
+    type Model = ValveMap;
 
+    const PARAMS: &'static [&'static str] = &["max_minutes", "elephant_init_time", "start"];
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_valve_map(input)
+    }
+
+    fn part1(valves: &Self::Model, params: &Params) -> Result<Answer> {
+        let valid = get_valid_valves(&valves.valve_map);
+        let start = get_start(valves, &params.get("start", START.to_string())?)?;
+        let max_minutes = params.get("max_minutes", MAX_MINUTES)?;
+        let dist = get_distances_valve(&valves.valve_map, &valves.tunnel_map);
+
+        // println!("dist={:?} / valid={:?}", dist, valid);
+        let maxed_transit =
+            get_maxed_transit(start, 0, max_minutes, &valves.valve_map, &dist, &valid);
+        // print_path(&maxed_transit, &valves.valve_map, &valves.encoded_valve_names, &dist);
+        Ok(maxed_transit.released_pressure.into())
+    }
+
+    fn part2(valves: &Self::Model, params: &Params) -> Result<Answer> {
+        let valid = get_valid_valves(&valves.valve_map);
+        let start = get_start(valves, &params.get("start", START.to_string())?)?;
+        let max_minutes = params.get("max_minutes", MAX_MINUTES)?;
+        let dist = get_distances_valve(&valves.valve_map, &valves.tunnel_map);
+
+        // println!("dist={:?} / valid={:?}", dist, valid);
+        // split valid into half and permutate. The two actors are independent from each other thus less balance graphs will be less optimal.
+        let elephant_init_time = params.get("elephant_init_time", ELEPHANT_INIT_TIME)?;
+        let divided_len = valid.len() / 2;
+        let mut max_release = 0;
+        for valid_split_vec in valid.iter().combinations(divided_len) {
+            let valid_split1: HashSet<usize> =
+                HashSet::from_iter(valid_split_vec.iter().map(|x| **x));
+            let valid_split2 = valid.difference(&valid_split1).copied().collect();
+            let maxed_transit1 = get_maxed_transit(
+                start,
+                elephant_init_time,
+                max_minutes,
+                &valves.valve_map,
+                &dist,
+                &valid_split1,
+            );
+            let maxed_transit2 = get_maxed_transit(
+                start,
+                elephant_init_time,
+                max_minutes,
+                &valves.valve_map,
+                &dist,
+                &valid_split2,
+            );
+            max_release = cmp::max(
+                max_release,
+                maxed_transit1.released_pressure + maxed_transit2.released_pressure,
+            );
+        }
+        Ok(max_release.into())
+    }
+}
+
+fn print_valves(valves: &ValveMap) {
     println!(
         "encoded_valve_names={:?} valve_map={:?}, tunnel_map={:?}",
-        encoded_valve_names, valve_map, tunnel_map
+        valves.encoded_valve_names, valves.valve_map, valves.tunnel_map
     );
-    // println!("dist={:?} / valid={:?}", dist, valid);
-    // split valid into half and permutate. The two actors are independent from each other thus less balance graphs will be less optimal.
-    let divided_len = valid.len() / 2;
-    let mut max_release = 0;
-    for valid_split_vec in valid.iter().combinations(divided_len) {
-        let valid_split1: HashSet<usize> =
-            HashSet::from_iter(valid_split_vec.iter().map(|x| *x).cloned());
-        let valid_split2 = valid.difference(&valid_split1).map(|x| *x).collect();
-        let maxed_transit1 =
-            get_maxed_transit(start, ELEPHANT_INIT_TIME, &valve_map, &dist, &valid_split1);
-        let maxed_transit2 =
-            get_maxed_transit(start, ELEPHANT_INIT_TIME, &valve_map, &dist, &valid_split2);
-        max_release = cmp::max(
-            max_release,
-            maxed_transit1.released_pressure + maxed_transit2.released_pressure,
+}
+
+pub fn get_max_flow() -> Result<()> {
+    let valves = Day16::parse(io::stdin().lock())?;
+    print_valves(&valves);
+    println!("{}", Day16::part1(&valves, &Params::default())?);
+    Ok(())
+}
+
+pub fn get_max_flow_with_elephant() -> Result<()> {
+    let valves = Day16::parse(io::stdin().lock())?;
+    print_valves(&valves);
+    println!("{}", Day16::part2(&valves, &Params::default())?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day16::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day16::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(1651)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day16::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day16::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(1707)
         );
     }
-    // print_path(&maxed_transit, &valve_map, &encoded_valve_names, &dist);
-    println!("{}", max_release);
 }
@@ -1,27 +1,66 @@
+use crate::error::Error;
+use crate::error::Result;
+use crate::grid::BitGrid;
+use crate::matrix;
+use crate::matrix::Matrix;
+use crate::matrix::Point;
+use crate::params::Params;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::collections::HashMap;
-use std::collections::VecDeque;
 use std::io;
+use std::io::BufRead;
 use std::vec::Vec;
 
-fn get_wind() -> Vec<bool> {
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let wind: Vec<bool> = line_str.chars().map(|x| x == '<').collect();
-        return wind;
+pub struct Day17;
+
+fn get_wind(input: impl BufRead) -> Result<Vec<bool>> {
+    let line_str = match input.lines().next() {
+        Some(line) => line?,
+        None => String::new(),
+    };
+    let mut wind = Vec::new();
+    for (i, c) in line_str.char_indices() {
+        match c {
+            '<' => wind.push(true),
+            '>' => wind.push(false),
+            _ => {
+                return Err(Error::unexpected_token(
+                    1,
+                    &line_str,
+                    &line_str[i..i + c.len_utf8()],
+                ))
+            }
+        }
+    }
+    if wind.is_empty() {
+        return Err(Error::malformed(1, &line_str, "expected a jet pattern"));
     }
-    vec![]
+    Ok(wind)
 }
 
 const WIDTH: usize = 7;
 const NUM_ROCKS: usize = 2022;
 const NUM_SPACE_BEFORE_ROCK: usize = 3;
 const ROCK_START_LEFT_PADDING: usize = 2;
+const ROCK_SHAPES: [&str; 5] = [
+    "####",
+    ".#.\n###\n.#.",
+    "..#\n..#\n###",
+    "#\n#\n#\n#",
+    "##\n##",
+];
 
 #[allow(dead_code, unused_imports)]
 fn print_world(world: &World) {
-    for layer in world.window.iter().rev() {
-        print!("|");
-        println!("{}|", layer.iter().collect::<String>());
+    for x in (0..world.window.rows()).rev() {
+        let layer: String = (0..world.width as i64)
+            .map(|y| match world.window.get(&Point { x: x as i64, y }) {
+                Some(true) => '#',
+                _ => '.',
+            })
+            .collect();
+        println!("|{}|", layer);
     }
 }
 
@@ -34,9 +73,11 @@ struct Rock {
 }
 
 struct World {
-    window: VecDeque<Vec<char>>,
-    rocks: Vec<Vec<Vec<char>>>,
+    window: BitGrid,
+    // each rock row as bits, column 0 being the lowest bit.
+    rocks_masks: Vec<Vec<u64>>,
     wind: Vec<bool>,
+    width: usize,
     rocks_heights: Vec<usize>,
     rocks_widths: Vec<usize>,
     chamber_height: usize,
@@ -46,28 +87,38 @@ struct World {
 }
 
 impl World {
-    pub fn new(wind: &Vec<bool>) -> Self {
-        let rocks = vec![
-            vec![vec!['#', '#', '#', '#']],
-            vec![
-                vec!['.', '#', '.'],
-                vec!['#', '#', '#'],
-                vec!['.', '#', '.'],
-            ],
-            vec![
-                vec!['#', '#', '#'],
-                vec!['.', '.', '#'],
-                vec!['.', '.', '#'],
-            ],
-            vec![vec!['#'], vec!['#'], vec!['#'], vec!['#']],
-            vec![vec!['#', '#'], vec!['#', '#']],
-        ];
-        let rocks_heights = rocks.iter().map(|rock| rock.len()).collect();
-        let rocks_widths = rocks.iter().map(|rock| rock[0].len()).collect();
+    pub fn new(wind: &[bool], width: usize) -> Self {
+        // drawn top row first, flipped so that row 0 is the bottom of the rock.
+        let rocks: Vec<Matrix<char>> = ROCK_SHAPES
+            .iter()
+            .map(|shape| {
+                let (rock, _) = matrix::from_chars(shape.as_bytes(), &[], |c| {
+                    matches!(c, '#' | '.').then_some(c)
+                })
+                .expect("the rock shapes are well formed");
+                matrix::flipud(&rock)
+            })
+            .collect();
+        let rocks_masks = rocks
+            .iter()
+            .map(|rock| {
+                matrix::rows(rock)
+                    .map(|row| {
+                        matrix::iter(&row)
+                            .enumerate()
+                            .filter(|(_, c)| **c == '#')
+                            .fold(0, |acc, (j, _)| acc | 1_u64 << j)
+                    })
+                    .collect()
+            })
+            .collect();
+        let rocks_heights = rocks.iter().map(|rock| rock.shape[0] as usize).collect();
+        let rocks_widths = rocks.iter().map(|rock| rock.shape[1] as usize).collect();
         Self {
-            window: VecDeque::new(),
-            rocks,
-            wind: wind.clone(),
+            window: BitGrid::new(0, width),
+            rocks_masks,
+            wind: wind.to_vec(),
+            width,
             chamber_height: 0,
             rocks_heights,
             rocks_widths,
@@ -91,9 +142,9 @@ impl World {
         let height = self.rocks_heights[selected];
         let width = self.rocks_widths[selected];
         let left = ROCK_START_LEFT_PADDING;
-        let bottom = self.window.len() + NUM_SPACE_BEFORE_ROCK + self.chamber_height;
+        let bottom = self.window.rows() + NUM_SPACE_BEFORE_ROCK + self.chamber_height;
 
-        self.cur_rock_idx = (self.cur_rock_idx + 1) % self.rocks.len();
+        self.cur_rock_idx = (self.cur_rock_idx + 1) % self.rocks_masks.len();
         self.num_rock += 1;
 
         Rock {
@@ -112,113 +163,70 @@ impl Rock {
         Self {
             selected,
             left: ROCK_START_LEFT_PADDING,
-            bottom: world.window.len() + NUM_SPACE_BEFORE_ROCK + world.chamber_height,
+            bottom: world.window.rows() + NUM_SPACE_BEFORE_ROCK + world.chamber_height,
             height: world.rocks_heights[selected],
             width: world.rocks_widths[selected],
         }
     }
 }
 
+// whether the rock shifted by `dx` columns and `dy` rows overlaps a settled rock.
+fn collides(world: &World, selected_rock: &Rock, dx: i64, dy: i64) -> bool {
+    let left = (selected_rock.left as i64 + dx) as usize;
+    let bottom = (selected_rock.bottom as i64 + dy) as usize;
+    world.rocks_masks[selected_rock.selected]
+        .iter()
+        .enumerate()
+        // rows above the window are empty.
+        .take_while(|(i, _)| i + bottom < world.window.rows() + world.chamber_height)
+        .any(|(i, mask)| {
+            world
+                .window
+                .row_intersects(i + bottom - world.chamber_height, &[mask << left])
+        })
+}
+
 fn shall_push_left(world: &World, selected_rock: &Rock) -> bool {
     // will the rock hit boundary?
-    if selected_rock.left <= 0 {
+    if selected_rock.left == 0 {
         return false;
     }
     // will the rock hit other things?
-    let rock = &world.rocks[selected_rock.selected];
-    for i in 0..selected_rock.height {
-        // not in the window?
-        if i + selected_rock.bottom >= world.window.len() + world.chamber_height {
-            break;
-        }
-
-        for j in 0..selected_rock.width {
-            if world.window[i + selected_rock.bottom - world.chamber_height]
-                [selected_rock.left - 1 + j]
-                == '#'
-                && rock[i][j] == '#'
-            {
-                return false;
-            }
-        }
-    }
-    true
+    !collides(world, selected_rock, -1, 0)
 }
 
 fn shall_push_right(world: &World, selected_rock: &Rock) -> bool {
     // will the rock hit boundary?
-    if selected_rock.left + selected_rock.width >= WIDTH {
+    if selected_rock.left + selected_rock.width >= world.width {
         return false;
     }
-    let rock = &world.rocks[selected_rock.selected];
-
     // will the rock hit other things?
-    for i in 0..selected_rock.height {
-        // not in the window?
-        if i + selected_rock.bottom >= world.window.len() + world.chamber_height {
-            break;
-        }
-
-        for j in (0..selected_rock.width).rev() {
-            if world.window[i + selected_rock.bottom - world.chamber_height]
-                [selected_rock.left + 1 + j]
-                == '#'
-                && rock[i][j] == '#'
-            {
-                return false;
-            }
-        }
-    }
-    true
+    !collides(world, selected_rock, 1, 0)
 }
 
 fn shall_fall(world: &World, selected_rock: &Rock) -> bool {
     // will the rock hit boundary?
-    if selected_rock.bottom <= 0 {
+    if selected_rock.bottom == 0 {
         return false;
     }
-    let rock = &world.rocks[selected_rock.selected];
-
     // will the rock hit other things?
-    for i in 0..selected_rock.height {
-        // not in the window?
-        if i + selected_rock.bottom - 1 >= world.window.len() + world.chamber_height {
-            break;
-        }
-
-        for j in 0..selected_rock.width {
-            if world.window[i + selected_rock.bottom - world.chamber_height - 1]
-                [selected_rock.left + j]
-                == '#'
-                && rock[i][j] == '#'
-            {
-                return false;
-            }
-        }
-    }
-    true
+    !collides(world, selected_rock, 0, -1)
 }
 
 fn insert_rock(world: &mut World, rock: &mut Rock) {
-    while world.window.len() < rock.bottom + rock.height - world.chamber_height {
-        world.window.push_back(vec!['.'; WIDTH]);
-    }
-    let rock_form = &world.rocks[rock.selected];
-    for i in 0..rock.height {
-        for j in 0..rock.width {
-            if rock_form[i][j] == '#' {
-                world.window[i + rock.bottom - world.chamber_height][j + rock.left] =
-                    rock_form[i][j];
-            }
-        }
+    while world.window.rows() < rock.bottom + rock.height - world.chamber_height {
+        world.window.push_row();
+    }
+    for (i, mask) in world.rocks_masks[rock.selected].iter().enumerate() {
+        world
+            .window
+            .or_row(i + rock.bottom - world.chamber_height, &[mask << rock.left]);
     }
 }
 
-pub fn simulate_tetris() {
-    let wind = get_wind();
-
-    let mut world = World::new(&wind);
-    while world.num_rock < NUM_ROCKS {
+fn get_tower_height(wind: &[bool], width: usize, num_rocks: usize) -> usize {
+    let mut world = World::new(wind, width);
+    while world.num_rock < num_rocks {
         let mut rock = world.next_rock();
 
         loop {
@@ -245,56 +253,57 @@ pub fn simulate_tetris() {
 
         // println!("\n{} landed:", world.num_rock);
         // print_world(&world);
-        // println!("{}", world.window.len());
+        // println!("{}", world.window.rows());
     }
 
-    println!("\n{} landed. size = {}", world.num_rock, world.window.len());
+    // println!("\n{} landed. size = {}", world.num_rock, world.window.rows());
     // print_world(&world);
+    world.window.rows()
 }
 
 const CACHE_LOOKBACK_WINDOW_LEN: usize = 25;
 const NUM_LONG_ROCKS: usize = 1000000000000;
 
-fn summary_window(window: &VecDeque<Vec<char>>, lookback_len: usize) -> Option<Vec<i64>> {
-    if window.len() < CACHE_LOOKBACK_WINDOW_LEN {
+fn summary_window(window: &BitGrid, lookback_len: usize) -> Option<Vec<i64>> {
+    if window.rows() < CACHE_LOOKBACK_WINDOW_LEN {
         return None;
     }
     let mut sum = Vec::new();
     for i in 0..lookback_len {
-        let win_idx = (window.len() as i64 - i as i64 - 1 as i64) as usize;
-        let row_sum = window[win_idx]
-            .iter()
-            .rev()
-            .fold(0, |acc, x| acc * 2 + (*x == '#') as i64);
+        let win_idx = (window.rows() as i64 - i as i64 - 1) as usize;
+        let row_sum = window.row(win_idx)[0] as i64;
         sum.push(row_sum);
     }
     Some(sum)
 }
 
-pub fn simulate_long_tetris() {
-    let wind = get_wind();
-
-    let mut world = World::new(&wind);
+fn get_long_tower_height(wind: &[bool], width: usize, num_rocks: usize) -> usize {
+    let mut world = World::new(wind, width);
     let mut cache: HashMap<usize, (Vec<i64>, usize, usize)> = HashMap::new();
-    while world.num_rock < NUM_LONG_ROCKS {
+    let mut skipped = false;
+    while world.num_rock < num_rocks {
         let mut rock = world.next_rock();
 
         let (wind_idx, mut push_left) = world.next_wind();
         let cache_key = rock.selected + wind_idx * 10;
-        if let Some(v) = cache.get(&cache_key) {
+        // the state is keyed before the rock falls, so a hit means the same rock meets the same
+        // jet on top of the same surface and everything from here on repeats.
+        if !skipped {
             if let Some(summary) = summary_window(&world.window, CACHE_LOOKBACK_WINDOW_LEN) {
-                if *v.0 == *summary {
-                    let diff_num_rock = world.num_rock - v.1;
-                    let diff_level = world.window.len() - v.2;
-                    println!("{} {:?}", world.num_rock, v);
-                    while world.num_rock <= NUM_LONG_ROCKS - diff_num_rock {
-                        world.num_rock += diff_num_rock;
-                        world.chamber_height += diff_level;
+                let level = world.window.rows() + world.chamber_height;
+                if let Some(v) = cache.get(&cache_key) {
+                    if v.0 == summary {
+                        let diff_num_rock = world.num_rock - v.1;
+                        let diff_level = level - v.2;
+                        let num_cycles = (num_rocks - world.num_rock) / diff_num_rock;
+                        world.num_rock += num_cycles * diff_num_rock;
+                        world.chamber_height += num_cycles * diff_level;
+                        rock.bottom =
+                            world.window.rows() + NUM_SPACE_BEFORE_ROCK + world.chamber_height;
+                        skipped = true;
                     }
-                    // assert!(false);
-                    // continue;
-                    rock.bottom = world.window.len() + NUM_SPACE_BEFORE_ROCK + world.chamber_height;
                 }
+                cache.insert(cache_key, (summary, world.num_rock, level));
             }
         }
         loop {
@@ -313,42 +322,78 @@ pub fn simulate_long_tetris() {
             } else {
                 break;
             }
-            // println!("\n{} rock: left={}, bottom={}", world.num_rock, rock.left, rock.bottom);
             (_, push_left) = world.next_wind();
         }
 
         insert_rock(&mut world, &mut rock);
-        // println!("\n{} landed:", world.num_rock);
-        // print_world(&world);
-        // println!("{}", world.window.len());
-        if let Some(summary) = summary_window(&world.window, CACHE_LOOKBACK_WINDOW_LEN) {
-            cache.insert(
-                cache_key,
-                (
-                    summary.clone(),
-                    world.num_rock,
-                    world.window.len() + world.chamber_height,
-                ),
-            );
-        }
     }
 
-    println!(
-        "\n{} landed. size = {}",
-        world.num_rock,
-        world.window.len() + world.chamber_height
-    );
-    // print_world(&world);
+    world.window.rows() + world.chamber_height
+}
+
+// the widest rock has to fit after the left padding, and a row is summarized as bits of an i64.
+fn get_width(params: &Params) -> Result<usize> {
+    let width = params.get("width", WIDTH)?;
+    if !(ROCK_START_LEFT_PADDING + 4..64).contains(&width) {
+        return Err(Error::invalid_state(
+            None,
+            &format!("the chamber cannot be {} units wide", width),
+        ));
+    }
+    Ok(width)
+}
+
+impl Solution for Day17 {
+    type Model = Vec<bool>;
+
+    const PARAMS: &'static [&'static str] = &["width", "num_rocks", "num_long_rocks"];
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_wind(input)
+    }
+
+    fn part1(wind: &Self::Model, params: &Params) -> Result<Answer> {
+        let width = get_width(params)?;
+        let num_rocks = params.get("num_rocks", NUM_ROCKS)?;
+        Ok(get_tower_height(wind, width, num_rocks).into())
+    }
+
+    fn part2(wind: &Self::Model, params: &Params) -> Result<Answer> {
+        let width = get_width(params)?;
+        let num_rocks = params.get("num_long_rocks", NUM_LONG_ROCKS)?;
+        Ok(get_long_tower_height(wind, width, num_rocks).into())
+    }
+}
+
+pub fn simulate_tetris() -> Result<()> {
+    let height = Day17::part1(&Day17::parse(io::stdin().lock())?, &Params::default())?;
+    println!("\n{} landed. size = {}", NUM_ROCKS, height);
+    Ok(())
+}
+
+pub fn simulate_long_tetris() -> Result<()> {
+    let height = Day17::part2(&Day17::parse(io::stdin().lock())?, &Params::default())?;
+    println!("\n{} landed. size = {}", NUM_LONG_ROCKS, height);
+    Ok(())
 }
 
 #[cfg(test)]
+#[allow(non_snake_case)]
 mod tests {
     use super::*;
 
+    fn push_layer(world: &mut World, layer: &str) {
+        world.window.push_row();
+        let x = world.window.rows() as i64 - 1;
+        for (y, c) in layer.chars().enumerate() {
+            world.window.set(&Point { x, y: y as i64 }, c == '#');
+        }
+    }
+
     #[test]
     fn test_shall_push_left__in_window() {
         let wind = vec![true, true, true, true];
-        let world = World::new(&wind);
+        let world = World::new(&wind, WIDTH);
         let rock = Rock::new(&world, 0);
         assert!(shall_push_left(&world, &rock));
     }
@@ -356,7 +401,7 @@ mod tests {
     #[test]
     fn test_shall_push_left__out_bound() {
         let wind = vec![true, true, true, true];
-        let world = World::new(&wind);
+        let world = World::new(&wind, WIDTH);
         let mut rock = Rock::new(&world, 0);
         rock.left = 0;
         assert!(!shall_push_left(&world, &rock));
@@ -365,13 +410,9 @@ mod tests {
     #[test]
     fn test_shall_push_left__when_collides() {
         let wind = vec![true, true, true, true];
-        let mut world = World::new(&wind);
-        world
-            .window
-            .push_back(vec!['#', '#', '.', '.', '.', '.', '.']);
-        world
-            .window
-            .push_back(vec!['#', '.', '.', '.', '.', '.', '.']);
+        let mut world = World::new(&wind, WIDTH);
+        push_layer(&mut world, "##.....");
+        push_layer(&mut world, "#......");
 
         let mut rock = Rock::new(&world, 1);
         rock.left = 1;
@@ -386,7 +427,7 @@ mod tests {
     #[test]
     fn test_shall_push_right__in_window() {
         let wind = vec![false; 4];
-        let world = World::new(&wind);
+        let world = World::new(&wind, WIDTH);
         let mut rock = Rock::new(&world, 0);
         rock.left = 2;
         assert!(shall_push_right(&world, &rock));
@@ -395,7 +436,7 @@ mod tests {
     #[test]
     fn test_shall_push_right__out_bound() {
         let wind = vec![false; 4];
-        let world = World::new(&wind);
+        let world = World::new(&wind, WIDTH);
         let mut rock = Rock::new(&world, 0);
         rock.left = 3;
         assert!(!shall_push_right(&world, &rock));
@@ -404,13 +445,9 @@ mod tests {
     #[test]
     fn test_shall_push_right__when_collides() {
         let wind = vec![false; 4];
-        let mut world = World::new(&wind);
-        world
-            .window
-            .push_back(vec!['.', '.', '.', '.', '.', '#', '#']);
-        world
-            .window
-            .push_back(vec!['.', '.', '.', '.', '.', '.', '#']);
+        let mut world = World::new(&wind, WIDTH);
+        push_layer(&mut world, ".....##");
+        push_layer(&mut world, "......#");
 
         let mut rock = Rock::new(&world, 1);
         rock.left = 3;
@@ -429,7 +466,7 @@ mod tests {
     #[test]
     fn test_shall_fall__in_window() {
         let wind = vec![false; 4];
-        let world = World::new(&wind);
+        let world = World::new(&wind, WIDTH);
         let mut rock = Rock::new(&world, 1);
         rock.bottom = 1;
         assert!(shall_fall(&world, &rock));
@@ -438,7 +475,7 @@ mod tests {
     #[test]
     fn test_shall_fall__out_bound() {
         let wind = vec![false; 4];
-        let world = World::new(&wind);
+        let world = World::new(&wind, WIDTH);
         let mut rock = Rock::new(&world, 0);
         rock.bottom = 0;
         assert!(!shall_fall(&world, &rock));
@@ -447,10 +484,8 @@ mod tests {
     #[test]
     fn test_shall_fall__when_collides() {
         let wind = vec![false; 4];
-        let mut world = World::new(&wind);
-        world
-            .window
-            .push_back(vec!['.', '.', '#', '.', '.', '.', '.']);
+        let mut world = World::new(&wind, WIDTH);
+        push_layer(&mut world, "..#....");
         let mut rock = Rock::new(&world, 1);
         rock.left = 1;
         rock.bottom = 1;
@@ -460,4 +495,25 @@ mod tests {
         rock.bottom = 1;
         assert!(shall_fall(&world, &rock));
     }
+
+    const EXAMPLE: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day17::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day17::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(3068)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day17::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day17::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(1514285714288)
+        );
+    }
 }
@@ -1,58 +1,129 @@
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::collections::HashMap;
 use std::io;
+use std::io::BufRead;
 
-pub fn score_by_guide() {
-    let resposne_scores = HashMap::from([("X", 1), ("Y", 2), ("Z", 3)]);
-
-    let game_outcome_scores: HashMap<&str, HashMap<&str, i32>> = HashMap::from([
-        ("X", HashMap::from([("A", 3), ("B", 0), ("C", 6)])),
-        ("Y", HashMap::from([("A", 6), ("B", 3), ("C", 0)])),
-        ("Z", HashMap::from([("A", 0), ("B", 6), ("C", 3)])),
-    ]);
-
-    let mut score = 0;
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let mut iter = line_str.as_str().split_whitespace().clone();
-        let opponent = iter.next().expect("Got opponent's response");
-        let mine = iter.next().expect("Got my response");
-        let response_score = resposne_scores.get(mine).expect("Got a score");
-        let outcome_score = game_outcome_scores
-            .get(mine)
-            .expect("Got an outcome map")
-            .get(opponent)
-            .expect("Got a score");
-        score += response_score + outcome_score;
+pub struct Day2;
+
+fn get_rounds(input: impl BufRead) -> Result<Vec<(String, String)>> {
+    let mut rounds = Vec::new();
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let mut iter = line_str.as_str().split_whitespace();
+        let opponent = iter
+            .next()
+            .ok_or_else(|| Error::malformed(line_no, &line_str, "missing opponent's response"))?;
+        let column = iter
+            .next()
+            .ok_or_else(|| Error::malformed(line_no, &line_str, "missing the second column"))?;
+        if !["A", "B", "C"].contains(&opponent) {
+            return Err(Error::unexpected_token(line_no, &line_str, opponent));
+        }
+        if !["X", "Y", "Z"].contains(&column) {
+            return Err(Error::unexpected_token(line_no, &line_str, column));
+        }
+        rounds.push((opponent.to_string(), column.to_string()));
     }
-    println!("Score: {score}");
+    Ok(rounds)
 }
 
-// it's easier to use string instead of hashmap
-pub fn score_by_secret_guide() {
-    let resposne_scores = HashMap::from([("A", 1), ("B", 2), ("C", 3)]);
-    let game_outcome_scores = HashMap::from([("X", 0), ("Y", 3), ("Z", 6)]);
-    let game_outcome_response = HashMap::from([
-        ("X", HashMap::from([("A", "C"), ("B", "A"), ("C", "B")])), // lose
-        ("Y", HashMap::from([("A", "A"), ("B", "B"), ("C", "C")])), // draw
-        ("Z", HashMap::from([("A", "B"), ("B", "C"), ("C", "A")])), // win
-    ]);
-
-    let mut score = 0;
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let mut iter = line_str.as_str().split_whitespace().clone();
-        let opponent = iter.next().expect("Got opponent's response");
-        let outcome = iter.next().expect("Got game outcome");
-        let response = game_outcome_response
-            .get(outcome)
-            .expect("Got an outcome response")
-            .get(opponent)
-            .expect("Got a response");
-        let response_score = resposne_scores.get(response).expect("get a response score");
-        let outcome_score = game_outcome_scores
-            .get(outcome)
-            .expect("get a outcome score");
-        score += response_score + outcome_score;
+impl Solution for Day2 {
+    type Model = Vec<(String, String)>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_rounds(input)
     }
+
+    fn part1(rounds: &Self::Model, _params: &Params) -> Result<Answer> {
+        let resposne_scores = HashMap::from([("X", 1), ("Y", 2), ("Z", 3)]);
+
+        let game_outcome_scores: HashMap<&str, HashMap<&str, i32>> = HashMap::from([
+            ("X", HashMap::from([("A", 3), ("B", 0), ("C", 6)])),
+            ("Y", HashMap::from([("A", 6), ("B", 3), ("C", 0)])),
+            ("Z", HashMap::from([("A", 0), ("B", 6), ("C", 3)])),
+        ]);
+
+        let mut score = 0;
+        for (opponent, mine) in rounds {
+            let response_score = resposne_scores.get(mine.as_str()).expect("Got a score");
+            let outcome_score = game_outcome_scores
+                .get(mine.as_str())
+                .expect("Got an outcome map")
+                .get(opponent.as_str())
+                .expect("Got a score");
+            score += response_score + outcome_score;
+        }
+        Ok(score.into())
+    }
+
+    // it's easier to use string instead of hashmap
+    fn part2(rounds: &Self::Model, _params: &Params) -> Result<Answer> {
+        let resposne_scores = HashMap::from([("A", 1), ("B", 2), ("C", 3)]);
+        let game_outcome_scores = HashMap::from([("X", 0), ("Y", 3), ("Z", 6)]);
+        let game_outcome_response = HashMap::from([
+            ("X", HashMap::from([("A", "C"), ("B", "A"), ("C", "B")])), // lose
+            ("Y", HashMap::from([("A", "A"), ("B", "B"), ("C", "C")])), // draw
+            ("Z", HashMap::from([("A", "B"), ("B", "C"), ("C", "A")])), // win
+        ]);
+
+        let mut score = 0;
+        for (opponent, outcome) in rounds {
+            let response = game_outcome_response
+                .get(outcome.as_str())
+                .expect("Got an outcome response")
+                .get(opponent.as_str())
+                .expect("Got a response");
+            let response_score = resposne_scores.get(response).expect("get a response score");
+            let outcome_score = game_outcome_scores
+                .get(outcome.as_str())
+                .expect("get a outcome score");
+            score += response_score + outcome_score;
+        }
+        Ok(score.into())
+    }
+}
+
+pub fn score_by_guide() -> Result<()> {
+    let score = Day2::part1(&Day2::parse(io::stdin().lock())?, &Params::default())?;
+    println!("Score: {score}");
+    Ok(())
+}
+
+pub fn score_by_secret_guide() -> Result<()> {
+    let score = Day2::part2(&Day2::parse(io::stdin().lock())?, &Params::default())?;
     println!("Score: {score}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "A Y
+B X
+C Z
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day2::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day2::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(15)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day2::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day2::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(12)
+        );
+    }
 }
@@ -1,83 +1,172 @@
 use crate::char_bins;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::io;
+use std::io::BufRead;
+
+pub struct Day3;
 
 fn remap_char_to_value(c: char) -> u32 {
-    return match c {
+    match c {
         'a'..='z' => c as u32 - 'a' as u32 + 1,
         'A'..='Z' => c as u32 - 'A' as u32 + 27,
         _ => 0,
-    } as u32;
+    }
 }
 
 fn get_occupancy(compartment: &str) -> [bool; 52] {
     let mut occupancy = [false; 52];
+    // rucksacks are checked to hold letters only while parsing
     for c in compartment.chars() {
-        match c {
-            'a'..='z' => {
-                occupancy[char_bins::remap_char_to_flatten_loc(c)] = true;
-            }
-            'A'..='Z' => {
-                occupancy[char_bins::remap_char_to_flatten_loc(c)] = true;
-            }
-            _ => todo!(),
-        };
+        occupancy[char_bins::remap_char_to_flatten_loc(c)] = true;
     }
-    return occupancy;
+    occupancy
 }
 
 fn same_item_by_compartment(rucksack: &str) -> Option<char> {
     let compartment_1 = &rucksack[0..rucksack.len() / 2];
     let compartment_2 = &rucksack[rucksack.len() / 2..rucksack.len()];
     let occupancy = get_occupancy(compartment_1);
-    for c in compartment_2.chars() {
-        if occupancy[char_bins::remap_char_to_flatten_loc(c)] {
-            return Some(c);
+    compartment_2
+        .chars()
+        .find(|&c| occupancy[char_bins::remap_char_to_flatten_loc(c)])
+}
+
+fn get_rucksacks(input: impl BufRead) -> Result<Vec<String>> {
+    let mut rucksacks = Vec::new();
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        if let Some((i, c)) = line_str
+            .char_indices()
+            .find(|(_, c)| !c.is_ascii_alphabetic())
+        {
+            return Err(Error::unexpected_token(
+                line_no,
+                &line_str,
+                &line_str[i..i + c.len_utf8()],
+            ));
+        }
+        if line_str.len() % 2 != 0 {
+            return Err(Error::malformed(
+                line_no,
+                &line_str,
+                "compartments differ in size",
+            ));
         }
+        rucksacks.push(line_str);
     }
-    return None;
+    Ok(rucksacks)
 }
 
-pub fn get_priorities() {
-    let mut priorities = 0;
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let c = same_item_by_compartment(line_str.as_str()).expect("Got an unique item");
-        priorities += remap_char_to_value(c);
+impl Solution for Day3 {
+    type Model = Vec<String>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_rucksacks(input)
     }
-    println!("priorities: {priorities}");
-}
 
-pub fn get_priorities_owned_by_3_elf() {
-    let mut priorities = 0;
-    let mut occupancy = [false; 52];
-    let mut group_item_cnt: i32 = 0;
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        if group_item_cnt == 0 {
-            occupancy = get_occupancy(line_str.as_str())
-        } else if group_item_cnt == 1 {
-            let mut c = 0;
-            occupancy = get_occupancy(line_str.as_str())
-                .into_iter()
-                .map(|e| {
+    fn part1(rucksacks: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut priorities = 0;
+        for (i, rucksack) in rucksacks.iter().enumerate() {
+            let c = same_item_by_compartment(rucksack.as_str())
+                .ok_or_else(|| Error::invalid_state(Some(i + 1), "no item in both compartments"))?;
+            priorities += remap_char_to_value(c);
+        }
+        Ok(priorities.into())
+    }
+
+    fn part2(rucksacks: &Self::Model, _params: &Params) -> Result<Answer> {
+        if rucksacks.len() % 3 != 0 {
+            return Err(Error::invalid_state(
+                Some(rucksacks.len()),
+                "last group has fewer than 3 elves",
+            ));
+        }
+        let mut priorities = 0;
+        let mut occupancy = [false; 52];
+        let mut group_item_cnt: i32 = 0;
+        for (i, rucksack) in rucksacks.iter().enumerate() {
+            if group_item_cnt == 0 {
+                occupancy = get_occupancy(rucksack.as_str())
+            } else if group_item_cnt == 1 {
+                let mut c = 0;
+                occupancy = get_occupancy(rucksack.as_str())
+                    .into_iter()
+                    .map(|e| {
+                        c += 1;
+                        e & occupancy[c - 1]
+                    })
+                    .collect::<Vec<bool>>()
+                    .try_into()
+                    .unwrap();
+            } else {
+                let mut c = 0;
+                let mut found = false;
+                for e in get_occupancy(rucksack.as_str()) {
                     c += 1;
-                    e & occupancy[c - 1]
-                })
-                .collect::<Vec<bool>>()
-                .try_into()
-                .unwrap();
-        } else {
-            let mut c = 0;
-            for e in get_occupancy(line_str.as_str()) {
-                c += 1;
-                if e & occupancy[c - 1] {
-                    priorities += c;
-                    break;
+                    if e & occupancy[c - 1] {
+                        priorities += c;
+                        found = true;
+                        break;
+                    }
+                }
+                if !found {
+                    return Err(Error::invalid_state(
+                        Some(i + 1),
+                        "no badge shared by the group",
+                    ));
                 }
+                group_item_cnt = -1;
             }
-            group_item_cnt = -1;
+            group_item_cnt += 1;
         }
-        group_item_cnt += 1;
+        Ok(priorities.into())
     }
+}
+
+pub fn get_priorities() -> Result<()> {
+    let priorities = Day3::part1(&Day3::parse(io::stdin().lock())?, &Params::default())?;
+    println!("priorities: {priorities}");
+    Ok(())
+}
+
+pub fn get_priorities_owned_by_3_elf() -> Result<()> {
+    let priorities = Day3::part2(&Day3::parse(io::stdin().lock())?, &Params::default())?;
     println!("priorities: {priorities}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day3::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day3::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(157)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day3::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day3::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(70)
+        );
+    }
 }
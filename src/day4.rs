@@ -1,55 +1,160 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::interval::IntervalSet;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::io;
+use std::io::BufRead;
+use std::ops::Range;
 use std::vec::Vec;
 
-fn analyze_assignment(assignment: &str) -> (i32, Vec<i32>) {
-    let seg: Vec<i32> = assignment
+pub struct Day4;
+
+// the sections as a half-open range.
+type Assignment = Range<i32>;
+
+fn analyze_assignment(assignment: &str, line_no: usize, line_str: &str) -> Result<Assignment> {
+    let seg = assignment
         .split('-')
-        .map(|e| e.parse::<i32>().expect("parse as an i32"))
-        .collect();
-    assert_eq!(seg.len(), 2);
-    return (seg[1] - seg[0] + 1, seg);
+        .map(|e| error::parse_token::<i32>(e, line_no, line_str))
+        .collect::<Result<Vec<i32>>>()?;
+    if seg.len() != 2 {
+        return Err(Error::malformed(
+            line_no,
+            line_str,
+            "an assignment is a range like 2-4",
+        ));
+    }
+    if seg[0] > seg[1] {
+        return Err(Error::malformed(
+            line_no,
+            line_str,
+            "an assignment cannot end before it starts",
+        ));
+    }
+    let end = seg[1].checked_add(1).ok_or_else(|| {
+        Error::malformed(
+            line_no,
+            line_str,
+            "an assignment ends past the largest section",
+        )
+    })?;
+    Ok(seg[0]..end)
 }
 
-fn segment_inclusion(segment: &str) -> bool {
-    let pairs: Vec<&str> = segment.split(',').collect();
-    assert_eq!(pairs.len(), 2);
-    let (len1, seg1) = analyze_assignment(pairs[0]);
-    let (len2, seg2) = analyze_assignment(pairs[1]);
-    if len1 <= len2 {
-        return seg2[1] >= seg1[1] && seg2[0] <= seg1[0];
-    } else {
-        return seg1[1] >= seg2[1] && seg1[0] <= seg2[0];
+fn get_assignment_pairs(input: impl BufRead) -> Result<Vec<(Assignment, Assignment)>> {
+    let mut pairs = Vec::new();
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let segments: Vec<&str> = line_str.split(',').collect();
+        if segments.len() != 2 {
+            return Err(Error::malformed(
+                line_no,
+                &line_str,
+                "expected a pair of assignments",
+            ));
+        }
+        pairs.push((
+            analyze_assignment(segments[0], line_no, &line_str)?,
+            analyze_assignment(segments[1], line_no, &line_str)?,
+        ));
     }
+    Ok(pairs)
 }
 
-fn segment_overlap(segment: &str) -> bool {
-    let pairs: Vec<&str> = segment.split(',').collect();
-    assert_eq!(pairs.len(), 2);
-    let (_len1, seg1) = analyze_assignment(pairs[0]);
-    let (_len2, seg2) = analyze_assignment(pairs[1]);
-    return seg1[1] >= seg2[0] && seg1[0] <= seg2[1];
+fn to_interval(seg: &Assignment) -> IntervalSet<i32> {
+    IntervalSet::from(seg.clone())
 }
 
-pub fn count_segments_inclusions() {
-    let mut cnt = 0;
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
+fn segment_inclusion(seg1: &Assignment, seg2: &Assignment) -> bool {
+    let (set1, set2) = (to_interval(seg1), to_interval(seg2));
+    set1.is_subset(&set2) || set2.is_subset(&set1)
+}
 
-        if segment_inclusion(line_str.as_str()) {
-            cnt += 1;
-        }
+fn segment_overlap(seg1: &Assignment, seg2: &Assignment) -> bool {
+    !to_interval(seg1)
+        .intersection(&to_interval(seg2))
+        .is_empty()
+}
+
+impl Solution for Day4 {
+    type Model = Vec<(Assignment, Assignment)>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_assignment_pairs(input)
+    }
+
+    fn part1(pairs: &Self::Model, _params: &Params) -> Result<Answer> {
+        let cnt = pairs
+            .iter()
+            .filter(|(seg1, seg2)| segment_inclusion(seg1, seg2))
+            .count();
+        Ok(cnt.into())
+    }
+
+    fn part2(pairs: &Self::Model, _params: &Params) -> Result<Answer> {
+        let cnt = pairs
+            .iter()
+            .filter(|(seg1, seg2)| segment_overlap(seg1, seg2))
+            .count();
+        Ok(cnt.into())
     }
+}
+
+pub fn count_segments_inclusions() -> Result<()> {
+    let cnt = Day4::part1(&Day4::parse(io::stdin().lock())?, &Params::default())?;
     println!("Count: {cnt}");
+    Ok(())
 }
 
-pub fn count_segments_overlap() {
-    let mut cnt = 0;
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
+pub fn count_segments_overlap() -> Result<()> {
+    let cnt = Day4::part2(&Day4::parse(io::stdin().lock())?, &Params::default())?;
+    println!("Count: {cnt}");
+    Ok(())
+}
 
-        if segment_overlap(line_str.as_str()) {
-            cnt += 1;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day4::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day4::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(2)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day4::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day4::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(4)
+        );
+    }
+
+    #[test]
+    fn test_invalid_assignments() {
+        assert!(matches!(
+            Day4::parse_str("2-4,6-8\n5-3,1-9\n"),
+            Err(Error::MalformedLine { line: 2, .. })
+        ));
+        assert!(matches!(
+            Day4::parse_str("1-2147483647,1-2\n"),
+            Err(Error::MalformedLine { line: 1, .. })
+        ));
     }
-    println!("Count: {cnt}");
 }
@@ -1,115 +1,217 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::io;
+use std::io::BufRead;
 use std::vec::Vec;
 
+pub struct Day5;
+
 enum State {
-    INITIAL,
-    INSTRUCTION,
+    Initial,
+    Instruction,
+}
+
+struct Move {
+    line: usize,
+    num_move: usize,
+    crate_from_id: usize,
+    crate_to_id: usize,
+}
+
+pub struct CrateYard {
+    stacks: Vec<Vec<char>>,
+    moves: Vec<Move>,
 }
 
-fn craete_stack(stacks: &mut Vec<Vec<char>>, stack_map: &mut Vec<String>) {
-    let num_stacks_str = stack_map.pop().expect("Get the last line of things");
+fn craete_stack(
+    stacks: &mut Vec<Vec<char>>,
+    stack_map: &mut Vec<String>,
+    line_no: usize,
+) -> Result<()> {
+    let num_stacks_str = stack_map
+        .pop()
+        .ok_or_else(|| Error::invalid_state(Some(line_no), "no crate stacks before the moves"))?;
     let num_stacks = num_stacks_str.as_str().split_whitespace().count();
     for _ in 0..num_stacks {
         stacks.push(Vec::new());
     }
     while let Some(craete_line) = stack_map.pop() {
-        for x in 0..num_stacks {
+        for (x, stack) in stacks.iter_mut().enumerate() {
             if x * 4 + 1 >= craete_line.len() {
                 break;
             }
-            let c = craete_line.chars().nth(x * 4 + 1).unwrap();
+            let c = craete_line.chars().nth(x * 4 + 1).unwrap_or(' ');
             if c != ' ' {
-                stacks[x].push(c);
+                stack.push(c);
             }
         }
     }
+    Ok(())
 }
 
-fn top_crates_in_stack(stacks: &Vec<Vec<char>>) -> String {
+fn top_crates_in_stack(stacks: &[Vec<char>]) -> Result<String> {
     let mut res = String::new();
-    for stack in stacks {
-        let item = stack.last().expect("Got an item");
+    for (i, stack) in stacks.iter().enumerate() {
+        let item = stack
+            .last()
+            .ok_or_else(|| Error::invalid_state(None, &format!("stack {} ends up empty", i + 1)))?;
         res.push(*item);
     }
-    return res;
+    Ok(res)
 }
 
-pub fn top_crate_after_moving() {
-    let mut state = State::INITIAL;
+fn get_move(line_no: usize, line_str: &str) -> Result<Move> {
+    let words: Vec<&str> = line_str.split_whitespace().collect();
+    if words.len() != 6 || words[0] != "move" || words[2] != "from" || words[4] != "to" {
+        return Err(Error::malformed(
+            line_no,
+            line_str,
+            "expected `move N from A to B`",
+        ));
+    }
+    Ok(Move {
+        line: line_no,
+        num_move: error::parse_token(words[1], line_no, line_str)?,
+        crate_from_id: error::parse_token(words[3], line_no, line_str)?,
+        crate_to_id: error::parse_token(words[5], line_no, line_str)?,
+    })
+}
+
+fn get_crate_yard(input: impl BufRead) -> Result<CrateYard> {
+    let mut state = State::Initial;
     let mut stack_map = Vec::new();
     let mut stacks = Vec::new();
+    let mut moves = Vec::new();
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-
-        if line_str == "" {
-            craete_stack(&mut stacks, &mut stack_map);
-            state = State::INSTRUCTION;
-            continue;
-        }
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
 
         match state {
-            State::INITIAL => {
-                stack_map.push(line_str);
+            State::Initial => {
+                if line_str.is_empty() {
+                    craete_stack(&mut stacks, &mut stack_map, line_no)?;
+                    state = State::Instruction;
+                } else {
+                    stack_map.push(line_str);
+                }
             }
-            State::INSTRUCTION => {
-                let c = line_str
-                    .as_str()
-                    .split_whitespace()
-                    .map(|e| e.parse::<i32>())
-                    .filter(|e| e.is_ok())
-                    .map(|e| e.unwrap() as usize)
-                    .collect::<Vec<_>>();
-                let (num_move, crate_from_id, crate_to_id) = (c[0], c[1], c[2]);
-                for _ in 0..num_move {
-                    let crate_ = stacks[crate_from_id - 1].pop().unwrap();
-                    stacks[crate_to_id - 1].push(crate_);
+            State::Instruction => {
+                if !line_str.is_empty() {
+                    moves.push(get_move(line_no, &line_str)?);
                 }
             }
         }
     }
-    let top_crates = top_crates_in_stack(&stacks);
-    println!("Top crates: {top_crates}");
+    Ok(CrateYard { stacks, moves })
 }
 
-pub fn top_crate_after_moving_with_new_crane() {
-    let mut state = State::INITIAL;
-    let mut stack_map = Vec::new();
-    let mut stacks = Vec::new();
+// pops `num_move` crates off the source stack, top first.
+fn take_crates(stacks: &mut [Vec<char>], step: &Move) -> Result<Vec<char>> {
+    let num_stacks = stacks.len();
+    for id in [step.crate_from_id, step.crate_to_id] {
+        if id == 0 || id > num_stacks {
+            return Err(Error::invalid_state(
+                Some(step.line),
+                &format!("there is no stack {}", id),
+            ));
+        }
+    }
+    let mut stage = Vec::new();
+    for _ in 0..step.num_move {
+        let crate_ = stacks[step.crate_from_id - 1].pop().ok_or_else(|| {
+            Error::invalid_state(
+                Some(step.line),
+                &format!("stack {} is empty", step.crate_from_id),
+            )
+        })?;
+        stage.push(crate_);
+    }
+    Ok(stage)
+}
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
+impl Solution for Day5 {
+    type Model = CrateYard;
 
-        if line_str == "" {
-            craete_stack(&mut stacks, &mut stack_map);
-            state = State::INSTRUCTION;
-            continue;
-        }
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_crate_yard(input)
+    }
 
-        match state {
-            State::INITIAL => {
-                stack_map.push(line_str);
+    fn part1(yard: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut stacks = yard.stacks.clone();
+        for step in &yard.moves {
+            for crate_ in take_crates(&mut stacks, step)? {
+                stacks[step.crate_to_id - 1].push(crate_);
             }
-            State::INSTRUCTION => {
-                let c = line_str
-                    .as_str()
-                    .split_whitespace()
-                    .map(|e| e.parse::<i32>())
-                    .filter(|e| e.is_ok())
-                    .map(|e| e.unwrap() as usize)
-                    .collect::<Vec<_>>();
-                let (num_move, crate_from_id, crate_to_id) = (c[0], c[1], c[2]);
-                let mut stage = Vec::new();
-                for _ in 0..num_move {
-                    let crate_ = stacks[crate_from_id - 1].pop().unwrap();
-                    stage.push(crate_);
-                }
-                while let Some(c) = stage.pop() {
-                    stacks[crate_to_id - 1].push(c);
-                }
+        }
+        Ok(top_crates_in_stack(&stacks)?.into())
+    }
+
+    fn part2(yard: &Self::Model, _params: &Params) -> Result<Answer> {
+        let mut stacks = yard.stacks.clone();
+        for step in &yard.moves {
+            let mut stage = take_crates(&mut stacks, step)?;
+            while let Some(c) = stage.pop() {
+                stacks[step.crate_to_id - 1].push(c);
             }
         }
+        Ok(top_crates_in_stack(&stacks)?.into())
     }
-    let top_crates = top_crates_in_stack(&stacks);
+}
+
+pub fn top_crate_after_moving() -> Result<()> {
+    let top_crates = Day5::part1(&Day5::parse(io::stdin().lock())?, &Params::default())?;
+    println!("Top crates: {top_crates}");
+    Ok(())
+}
+
+pub fn top_crate_after_moving_with_new_crane() -> Result<()> {
+    let top_crates = Day5::part2(&Day5::parse(io::stdin().lock())?, &Params::default())?;
     println!("Top crates: {top_crates}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day5::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day5::part1(&model, &Params::default()).unwrap(),
+            Answer::from("CMZ")
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day5::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day5::part2(&model, &Params::default()).unwrap(),
+            Answer::from("MCD")
+        );
+    }
+    #[test]
+    fn test_missing_stacks() {
+        assert!(matches!(
+            Day5::parse_str("\nmove 1 from 1 to 2\n"),
+            Err(Error::InvalidState { line: Some(1), .. })
+        ));
+    }
 }
@@ -1,48 +1,107 @@
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::io;
-use std::process;
+use std::io::BufRead;
+
+pub struct Day6;
 
 const WINDOW_SIZE: usize = 4;
 const MESSAGE_WINDOW_SIZE: usize = 14;
 
-pub fn first_marker() {
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-
-        let mut window = VecDeque::new();
-        let mut count = 0;
-        for c in line_str.chars() {
-            count += 1;
-            window.push_back(c);
-            if window.len() >= WINDOW_SIZE {
-                let window_set: HashSet<char> = HashSet::from_iter(window.iter().cloned());
-                if window_set.len() == WINDOW_SIZE {
-                    println!("Marker: {count}");
-                    process::exit(0);
-                }
-                window.pop_front().expect("Got the first element");
+fn get_datastreams(input: impl BufRead) -> Result<Vec<String>> {
+    Ok(input.lines().collect::<std::io::Result<Vec<String>>>()?)
+}
+
+fn find_marker(datastream: &str, window_size: usize) -> Option<usize> {
+    let mut window = VecDeque::new();
+    let mut count = 0;
+    for c in datastream.chars() {
+        count += 1;
+        window.push_back(c);
+        if window.len() >= window_size {
+            let window_set: HashSet<char> = HashSet::from_iter(window.iter().cloned());
+            if window_set.len() == window_size {
+                return Some(count);
             }
+            window.pop_front().expect("Got the first element");
         }
     }
+    None
 }
-pub fn first_marker_for_message() {
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-
-        let mut window = VecDeque::new();
-        let mut count = 0;
-        for c in line_str.chars() {
-            count += 1;
-            window.push_back(c);
-            if window.len() >= MESSAGE_WINDOW_SIZE {
-                let window_set: HashSet<char> = HashSet::from_iter(window.iter().cloned());
-                if window_set.len() == MESSAGE_WINDOW_SIZE {
-                    println!("Marker: {count}");
-                    process::exit(0);
-                }
-                window.pop_front().expect("Got the first element");
-            }
+
+impl Solution for Day6 {
+    type Model = Vec<String>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_datastreams(input)
+    }
+
+    fn part1(datastreams: &Self::Model, _params: &Params) -> Result<Answer> {
+        let count = datastreams
+            .iter()
+            .find_map(|d| find_marker(d, WINDOW_SIZE))
+            .ok_or_else(|| Error::invalid_state(None, "no marker in the datastream"))?;
+        Ok(count.into())
+    }
+
+    fn part2(datastreams: &Self::Model, _params: &Params) -> Result<Answer> {
+        let count = datastreams
+            .iter()
+            .find_map(|d| find_marker(d, MESSAGE_WINDOW_SIZE))
+            .ok_or_else(|| Error::invalid_state(None, "no marker in the datastream"))?;
+        Ok(count.into())
+    }
+}
+
+pub fn first_marker() -> Result<()> {
+    let count = Day6::part1(&Day6::parse(io::stdin().lock())?, &Params::default())?;
+    println!("Marker: {count}");
+    Ok(())
+}
+
+pub fn first_marker_for_message() -> Result<()> {
+    let count = Day6::part2(&Day6::parse(io::stdin().lock())?, &Params::default())?;
+    println!("Marker: {count}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (datastream, start-of-packet marker, start-of-message marker)
+    const EXAMPLES: [(&str, i64, i64); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn test_part1_example() {
+        for (datastream, packet, _) in EXAMPLES {
+            let model = Day6::parse_str(datastream).unwrap();
+            assert_eq!(
+                Day6::part1(&model, &Params::default()).unwrap(),
+                Answer::Number(packet)
+            );
+        }
+    }
+
+    #[test]
+    fn test_part2_example() {
+        for (datastream, _, message) in EXAMPLES {
+            let model = Day6::parse_str(datastream).unwrap();
+            assert_eq!(
+                Day6::part2(&model, &Params::default()).unwrap(),
+                Answer::Number(message)
+            );
         }
     }
 }
@@ -1,7 +1,15 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
 use std::io;
+use std::io::BufRead;
 use std::rc::Rc;
 use std::rc::Weak;
 use std::vec::Vec;
@@ -21,8 +29,10 @@ impl fmt::Display for FileNode {
     }
 }
 
+pub struct Day7;
+
 #[derive(Debug)]
-struct FileTreeNode {
+pub struct FileTreeNode {
     name: String,
     node_type: FileNode,
     parent: Weak<RefCell<FileTreeNode>>,
@@ -32,7 +42,39 @@ struct FileTreeNode {
 
 const ROOT: &str = "/";
 const DIR_TOTAL_SIZE_UPPER_THRESHOLD: u64 = 100000;
-fn build_fs_tree() -> Rc<RefCell<FileTreeNode>> {
+fn get_cd_dir(line_no: usize, line_str: &str) -> Result<String> {
+    let words: Vec<&str> = line_str.split_whitespace().collect();
+    if words.len() != 3 {
+        return Err(Error::malformed(
+            line_no,
+            line_str,
+            "expected `$ cd <directory>`",
+        ));
+    }
+    Ok(words[2].to_string())
+}
+
+fn get_ls_entry(line_no: usize, line_str: &str) -> Result<(String, FileNode)> {
+    let mut splits = line_str.split_whitespace();
+    let (dir_or_size, name) = match (splits.next(), splits.next()) {
+        (Some(dir_or_size), Some(name)) => (dir_or_size, name),
+        _ => {
+            return Err(Error::malformed(
+                line_no,
+                line_str,
+                "expected `dir <name>` or `<size> <name>`",
+            ))
+        }
+    };
+    let node_type = if dir_or_size == "dir" {
+        FileNode::Directory
+    } else {
+        FileNode::File(error::parse_token::<u64>(dir_or_size, line_no, line_str)?)
+    };
+    Ok((name.to_string(), node_type))
+}
+
+fn build_fs_tree(input: impl BufRead) -> Result<Rc<RefCell<FileTreeNode>>> {
     let root = Rc::new(RefCell::new(FileTreeNode {
         name: String::from(ROOT),
         node_type: FileNode::Directory,
@@ -43,15 +85,11 @@ fn build_fs_tree() -> Rc<RefCell<FileTreeNode>> {
     let mut processing_ls_output = false;
     let mut ptr = Rc::clone(&root);
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
         if line_str.starts_with("$ cd") {
             processing_ls_output = false;
-            let cd_dir = line_str
-                .as_str()
-                .split_whitespace()
-                .last()
-                .expect("get a directory");
+            let cd_dir = get_cd_dir(line_no, &line_str)?;
             if cd_dir == ROOT {
                 ptr = Rc::clone(&root);
             } else if cd_dir == ".." {
@@ -61,55 +99,53 @@ fn build_fs_tree() -> Rc<RefCell<FileTreeNode>> {
                 };
             } else {
                 let iter_ptr = Rc::clone(&ptr);
-                for child_cell in &(*iter_ptr).borrow().children {
-                    let child = child_cell.borrow();
-                    if child.name == cd_dir {
-                        ptr = Rc::clone(child_cell);
-                        break;
-                    }
-                }
+                let found = (*iter_ptr)
+                    .borrow()
+                    .children
+                    .iter()
+                    .find(|child_cell| child_cell.borrow().name == cd_dir)
+                    .cloned();
+                ptr = found.ok_or_else(|| {
+                    Error::invalid_state(
+                        Some(line_no),
+                        &format!("cd into {} before it was listed", cd_dir),
+                    )
+                })?;
             }
         } else if line_str == "$ ls" {
             processing_ls_output = true;
+        } else if line_str.starts_with('$') {
+            return Err(Error::unexpected_token(line_no, &line_str, &line_str));
+        } else if processing_ls_output {
+            let (name, node_type) = get_ls_entry(line_no, &line_str)?;
+            let new_node = FileTreeNode {
+                name,
+                node_type,
+                parent: Rc::downgrade(&ptr),
+                children: Vec::new(),
+                total_size: 0,
+            };
+            (*ptr)
+                .borrow_mut()
+                .children
+                .push(Rc::new(RefCell::new(new_node)));
         } else {
-            if processing_ls_output {
-                let mut splits = line_str.as_str().split_whitespace();
-                let dir_or_size = splits.next().expect("Got dir or a size");
-                let name = String::from(splits.next().expect("Got a name"));
-                let is_dir = dir_or_size == "dir";
-                let new_node = if is_dir {
-                    FileTreeNode {
-                        name,
-                        node_type: FileNode::Directory,
-                        parent: Rc::downgrade(&ptr),
-                        children: Vec::new(),
-                        total_size: 0,
-                    }
-                } else {
-                    FileTreeNode {
-                        name,
-                        node_type: FileNode::File(dir_or_size.parse::<u64>().expect("Got a size")),
-                        parent: Rc::downgrade(&ptr),
-                        children: Vec::new(),
-                        total_size: 0,
-                    }
-                };
-                (*ptr)
-                    .borrow_mut()
-                    .children
-                    .push(Rc::new(RefCell::new(new_node)));
-            }
+            return Err(Error::malformed(
+                line_no,
+                &line_str,
+                "listing output without `$ ls`",
+            ));
         }
     }
     sum_fs_tree(Rc::clone(&root));
-    return root;
+    Ok(root)
 }
 
 fn print_fs_tree(ptr: Rc<RefCell<FileTreeNode>>, level: usize) {
     let node = (*ptr).borrow();
     let lead = "-";
     let blanks = "  ".repeat(level);
-    let leading_chars = blanks + &lead.to_string();
+    let leading_chars = blanks + lead;
     println!(
         "{} {} {} | (total_size={})",
         leading_chars, node.name, node.node_type, node.total_size
@@ -131,7 +167,7 @@ fn sum_fs_dir_upper_bound(ptr: Rc<RefCell<FileTreeNode>>) -> u64 {
     for child in &node.children {
         sum += sum_fs_dir_upper_bound(Rc::clone(child));
     }
-    return sum;
+    sum
 }
 
 fn sum_fs_tree(ptr: Rc<RefCell<FileTreeNode>>) -> u64 {
@@ -144,61 +180,40 @@ fn sum_fs_tree(ptr: Rc<RefCell<FileTreeNode>>) -> u64 {
         .children
         .iter()
         .fold(0, |sum, val| sum + sum_fs_tree(Rc::clone(val)));
-    return node.total_size;
+    node.total_size
 }
 
 #[allow(dead_code, unused_imports)]
-fn build_fs_tree_in_hash_map() -> HashMap<String, FileNode> {
+fn build_fs_tree_in_hash_map(input: impl BufRead) -> Result<HashMap<String, FileNode>> {
     let mut fs = HashMap::new();
     fs.insert(String::from(ROOT), FileNode::Directory);
-    let mut path_stack = Vec::new();
-    path_stack.push(String::from(""));
+    let mut path_stack = vec![String::from("")];
     let mut processing_ls_output = false;
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
         if line_str.starts_with("$ cd") {
             processing_ls_output = false;
-            let cd_dir = line_str
-                .as_str()
-                .split_whitespace()
-                .last()
-                .expect("get a directory");
+            let cd_dir = get_cd_dir(line_no, &line_str)?;
             if cd_dir == ROOT {
                 path_stack = Vec::new();
                 path_stack.push(String::from(""));
             } else if cd_dir == ".." {
                 path_stack.pop();
             } else {
-                path_stack.push(String::from(cd_dir));
+                path_stack.push(cd_dir);
             }
         } else if line_str == "$ ls" {
             processing_ls_output = true;
-        } else {
-            if processing_ls_output {
-                let mut splits = line_str.as_str().split_whitespace();
-                let dir_or_size = splits.next().expect("Got dir or a size");
-                let name = splits.next().expect("Got a name");
-                let is_dir = dir_or_size == "dir";
-                path_stack.push(String::from(name));
-                let path = path_stack.join("/");
-                path_stack.pop();
-                if is_dir {
-                    fs.insert(path, FileNode::Directory);
-                } else {
-                    let size = dir_or_size.parse::<u64>().expect("Got a value");
-                    fs.insert(path, FileNode::File(size));
-                }
-            }
+        } else if processing_ls_output {
+            let (name, node_type) = get_ls_entry(line_no, &line_str)?;
+            path_stack.push(name);
+            let path = path_stack.join("/");
+            path_stack.pop();
+            fs.insert(path, node_type);
         }
     }
-    return fs;
-}
-
-pub fn sum_bound_dirs() {
-    let root = build_fs_tree();
-    print_fs_tree(Rc::clone(&root), 0);
-    println!("{}", sum_fs_dir_upper_bound(Rc::clone(&root)));
+    Ok(fs)
 }
 
 fn find_fs_dir_to_remove(
@@ -224,14 +239,89 @@ fn find_fs_dir_to_remove(
 
 const ALLOWED_USED_SPACE: u64 = 70000000 - 30000000;
 
-pub fn smallest_dirs_remove() {
-    let root = build_fs_tree();
+impl Solution for Day7 {
+    type Model = Rc<RefCell<FileTreeNode>>;
+
+    const PARAMS: &'static [&'static str] = &["allowed_used_space"];
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        build_fs_tree(input)
+    }
+
+    fn part1(root: &Self::Model, _params: &Params) -> Result<Answer> {
+        Ok(sum_fs_dir_upper_bound(Rc::clone(root)).into())
+    }
+
+    fn part2(root: &Self::Model, params: &Params) -> Result<Answer> {
+        let used_space = (*Rc::clone(root)).borrow().total_size;
+        let allowed_used_space = params.get("allowed_used_space", ALLOWED_USED_SPACE)?;
+        let lower_bound = used_space.saturating_sub(allowed_used_space);
+
+        let mut lowest = Rc::clone(root);
+        find_fs_dir_to_remove(Rc::clone(root), &mut lowest, lower_bound);
+        let size = (*lowest).borrow().total_size;
+        Ok(size.into())
+    }
+}
+
+pub fn sum_bound_dirs() -> Result<()> {
+    let root = Day7::parse(io::stdin().lock())?;
     print_fs_tree(Rc::clone(&root), 0);
+    println!("{}", Day7::part1(&root, &Params::default())?);
+    Ok(())
+}
+
+pub fn smallest_dirs_remove() -> Result<()> {
+    let root = Day7::parse(io::stdin().lock())?;
+    print_fs_tree(Rc::clone(&root), 0);
+    println!("{}", Day7::part2(&root, &Params::default())?);
+    Ok(())
+}
 
-    let used_space = (*Rc::clone(&root)).borrow().total_size;
-    let lower_bound = used_space - ALLOWED_USED_SPACE;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut lowest = Rc::clone(&root);
-    find_fs_dir_to_remove(Rc::clone(&root), &mut lowest, lower_bound);
-    println!("{}", (*lowest).borrow().total_size);
+    const EXAMPLE: &str = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day7::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day7::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(95437)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day7::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day7::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(24933642)
+        );
+    }
 }
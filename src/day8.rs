@@ -1,86 +1,44 @@
-use crate::char_bins;
+use crate::error::Error;
+use crate::error::Result;
 use crate::matrix;
+use crate::matrix::Matrix;
+use crate::params::Params;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::cmp;
 use std::io;
+use std::io::BufRead;
+use std::ops::Range;
+use std::thread;
 use std::vec::Vec;
 
-fn get_treemap() -> Vec<Vec<usize>> {
-    let mut treemap = Vec::new();
+pub struct Day8;
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let row = line_str
-            .chars()
-            .map(|c| char_bins::remap_char_to_flatten_loc(c))
-            .collect();
-        treemap.push(row);
+fn get_treemap(input: impl BufRead) -> Result<Matrix<usize>> {
+    let (treemap, _) = matrix::from_chars(input, &[], |c| c.to_digit(10).map(|d| d as usize))?;
+    if treemap.shape[0] == 0 || treemap.shape[1] == 0 {
+        return Err(Error::invalid_state(None, "the forest is empty"));
     }
-    return treemap;
+    Ok(treemap)
 }
 
-/// 4 scanning directions:
-///   - max from top to bottom (get_treemap_masks_positive/false)
-///   - max from left to right (get_treemap_masks_positive/true)
-///   - max from bottom to top (get_treemap_masks_negative/false)
-///   - max from right to left (get_treemap_masks_negative/true)
-///
-///   - increasing length from top to bottom (get_treemap_increasing_length_positive/false)
-///   - increasing length from left to right (get_treemap_increasing_length_positive/true)
-///   - increasing length from bottom to top (get_treemap_increasing_length_negative/false)
-///   - increasing length from right to left (get_treemap_increasing_length_negative/true)
-const DIRECTIONS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
-
-fn get_treemap_masks_positive(treemap: &Vec<Vec<usize>>, column: bool) -> Vec<Vec<usize>> {
-    let direction = column as usize;
-    let mut mask = treemap.clone();
-    let len_row = treemap.len() as i64;
-    let len_column = treemap[0].len() as i64;
-    for i in DIRECTIONS[direction].0..len_row {
-        for j in DIRECTIONS[direction].1..len_column {
-            mask[i as usize][j as usize] = cmp::max(
-                mask[i as usize][j as usize],
-                mask[(i - DIRECTIONS[direction].0) as usize]
-                    [(j - DIRECTIONS[direction].1) as usize],
-            );
-        }
-    }
-    return mask;
-}
-
-fn get_treemap_masks_negative(treemap: &Vec<Vec<usize>>, column: bool) -> Vec<Vec<usize>> {
-    let direction = column as usize + 2;
-    let mut mask = treemap.clone();
-    let len_row = treemap.len() as i64;
-    let len_column = treemap[0].len() as i64;
-    for i in (0..len_row + DIRECTIONS[direction].0).rev() {
-        for j in (0..len_column + DIRECTIONS[direction].1).rev() {
-            mask[i as usize][j as usize] = cmp::max(
-                mask[i as usize][j as usize],
-                mask[(i - DIRECTIONS[direction].0) as usize]
-                    [(j - DIRECTIONS[direction].1) as usize],
-            );
-        }
-    }
-    return mask;
+// with fewer than 3 rows or columns there is no inner tree; all of them are on the edge.
+fn is_all_edge(treemap: &Matrix<usize>) -> bool {
+    treemap.shape[0] < 3 || treemap.shape[1] < 3
 }
 
 const NUM_DIGITS: usize = 10;
 
-fn get_least_equal_distances(treemap: &matrix::Matrix<usize>) -> Vec<Vec<usize>> {
-    let last_dim = *treemap.shape.last().expect("Got last dimension");
-    let first_dim = *treemap.shape.first().expect("Got first dimension");
-
+fn get_least_equal_distances(treemap: &Matrix<usize>) -> Vec<Vec<usize>> {
     let mut distances_treemap = Vec::new();
-    for i in 0..first_dim {
-        let mut seen_index = [-1 as i64; NUM_DIGITS];
-        let mut distances = vec![0 as usize];
-        seen_index[matrix::index(&treemap, &[i as usize, 0 as usize])] = 0;
-        for j in 1..last_dim {
-            let ele = matrix::index(&treemap, &[i as usize, j as usize]);
-            let mut distance: usize = j as usize;
-            for seen_tree in ele..NUM_DIGITS {
-                if seen_index[seen_tree] >= 0 {
-                    distance = cmp::min(distance, (j as i64 - seen_index[seen_tree]) as usize);
+    for row in matrix::rows(treemap) {
+        let mut seen_index = [-1_i64; NUM_DIGITS];
+        let mut distances = Vec::new();
+        for (j, &ele) in matrix::iter(&row).enumerate() {
+            let mut distance: usize = j;
+            for &seen in &seen_index[ele..] {
+                if seen >= 0 {
+                    distance = cmp::min(distance, (j as i64 - seen) as usize);
                 }
             }
             seen_index[ele] = j as i64;
@@ -88,93 +46,192 @@ fn get_least_equal_distances(treemap: &matrix::Matrix<usize>) -> Vec<Vec<usize>>
         }
         distances_treemap.push(distances);
     }
-    return distances_treemap;
+    distances_treemap
 }
 
-fn reshape_treemap_boundary(treemap: &Vec<Vec<usize>>) -> Vec<Vec<usize>> {
-    let len_row = treemap.len() as usize;
-    let len_column = treemap[0].len() as usize;
-    let mut new_treemap = Vec::new();
-    for i in 0..len_row {
-        let mut row = Vec::new();
-        for j in 0..len_column {
-            row.push(treemap[i][j] as usize);
-        }
-        new_treemap.push(row);
+fn reshape_treemap_boundary(treemap: &Matrix<usize>) -> Matrix<usize> {
+    let len_row = treemap.shape[0] as usize;
+    let len_column = treemap.shape[1] as usize;
+    let (rows, columns) = (1..len_row - 1, 1..len_column - 1);
+    // a border tree takes the max with its inner neighbour; corners are kept.
+    let border_max = |border: &[Range<usize>], inner: &[Range<usize>]| {
+        matrix::zip_with(
+            &matrix::crop(treemap, border),
+            &matrix::crop(treemap, inner),
+            |b, i| *cmp::max(b, i),
+        )
+    };
+    let top = border_max(&[0..1, columns.clone()], &[1..2, columns.clone()]);
+    let bottom = border_max(
+        &[len_row - 1..len_row, columns.clone()],
+        &[len_row - 2..len_row - 1, columns.clone()],
+    );
+    let left = border_max(&[rows.clone(), 0..1], &[rows.clone(), 1..2]);
+    let right = border_max(
+        &[rows.clone(), len_column - 1..len_column],
+        &[rows.clone(), len_column - 2..len_column - 1],
+    );
+    let inner = matrix::crop(treemap, &[rows, columns]);
+    let corner = |i: usize, j: usize| matrix::crop(treemap, &[i..i + 1, j..j + 1]);
+    matrix::concat(
+        &[
+            &matrix::concat(&[&corner(0, 0), &top, &corner(0, len_column - 1)], 1),
+            &matrix::concat(&[&left, &inner, &right], 1),
+            &matrix::concat(
+                &[
+                    &corner(len_row - 1, 0),
+                    &bottom,
+                    &corner(len_row - 1, len_column - 1),
+                ],
+                1,
+            ),
+        ],
+        0,
+    )
+}
+
+impl Solution for Day8 {
+    type Model = Matrix<usize>;
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_treemap(input)
     }
-    for j in 1..len_column - 1 {
-        new_treemap[0][j] = cmp::max(new_treemap[0][j], new_treemap[1][j]);
-        new_treemap[len_row - 1][j] =
-            cmp::max(new_treemap[len_row - 1][j], new_treemap[len_row - 2][j]);
+
+    fn part1(treemap: &Self::Model, _params: &Params) -> Result<Answer> {
+        Ok(count_visible_trees(treemap).into())
     }
-    for i in 1..len_row - 1 {
-        new_treemap[i][0] = cmp::max(new_treemap[i][0], new_treemap[i][1]);
-        new_treemap[i][len_column - 1] = cmp::max(
-            new_treemap[i][len_column - 1],
-            new_treemap[i][len_column - 2],
-        );
+
+    fn part2(treemap: &Self::Model, _params: &Params) -> Result<Answer> {
+        Ok(get_max_scenic_score(treemap).into())
     }
-    return new_treemap;
 }
 
-pub fn sum_visible_trees() {
-    let treemap = get_treemap();
-    let len_row = treemap.len();
-    let len_column = treemap[0].len();
+fn count_visible_trees(treemap: &Matrix<usize>) -> usize {
+    let len_row = treemap.shape[0] as usize;
+    let len_column = treemap.shape[1] as usize;
+    if is_all_edge(treemap) {
+        return len_row * len_column;
+    }
     let mut sum = 2 * len_row + 2 * len_column - 4;
-    let max_from_top_to_bottom = get_treemap_masks_positive(&treemap, false);
-    let max_from_left_to_right = get_treemap_masks_positive(&treemap, true);
-    let max_from_bottom_to_top = get_treemap_masks_negative(&treemap, false);
-    let max_from_right_to_left = get_treemap_masks_negative(&treemap, true);
+    let max_from_top_to_bottom = matrix::cummax(treemap, 0, false);
+    let max_from_left_to_right = matrix::cummax(treemap, 1, false);
+    let max_from_bottom_to_top = matrix::cummax(treemap, 0, true);
+    let max_from_right_to_left = matrix::cummax(treemap, 1, true);
 
     for i in 1..len_row - 1 {
         for j in 1..len_column - 1 {
-            let cur = treemap[i][j];
-            let visible = cur > max_from_top_to_bottom[i - 1][j]
-                || cur > max_from_left_to_right[i][j - 1]
-                || cur > max_from_bottom_to_top[i + 1][j]
-                || cur > max_from_right_to_left[i][j + 1];
+            let cur = matrix::index(treemap, &[i, j]);
+            let visible = cur > matrix::index(&max_from_top_to_bottom, &[i - 1, j])
+                || cur > matrix::index(&max_from_left_to_right, &[i, j - 1])
+                || cur > matrix::index(&max_from_bottom_to_top, &[i + 1, j])
+                || cur > matrix::index(&max_from_right_to_left, &[i, j + 1]);
             if visible {
                 sum += 1;
             }
         }
     }
+    sum
+}
+
+fn get_max_scenic_score(treemap: &Matrix<usize>) -> usize {
+    if is_all_edge(treemap) {
+        return 0;
+    }
+    let treemap_matrix = reshape_treemap_boundary(treemap);
+    // the four directions are independent, so each one is scanned on its own thread.
+    let (distance_up_matrix, distance_down_matrix, distance_left_matrix, distance_right_matrix) =
+        thread::scope(|scope| {
+            let up = scope.spawn(|| {
+                matrix::rot90(
+                    &matrix::from_2d(&get_least_equal_distances(&matrix::rot90(
+                        &treemap_matrix,
+                        1,
+                    ))),
+                    3,
+                )
+            });
+            let down = scope.spawn(|| {
+                matrix::flipud(&matrix::rot90(
+                    &matrix::from_2d(&get_least_equal_distances(&matrix::rot90(
+                        &matrix::flipud(&treemap_matrix),
+                        1,
+                    ))),
+                    3,
+                ))
+            });
+            let left = scope.spawn(|| matrix::from_2d(&get_least_equal_distances(&treemap_matrix)));
+            let right = scope.spawn(|| {
+                matrix::fliplr(&matrix::from_2d(&get_least_equal_distances(
+                    &matrix::fliplr(&treemap_matrix),
+                )))
+            });
+            (
+                up.join().expect("up scan"),
+                down.join().expect("down scan"),
+                left.join().expect("left scan"),
+                right.join().expect("right scan"),
+            )
+        });
+
+    // boundary trees see nothing in one direction, so their score is 0.
+    let scores =
+        distance_up_matrix * &distance_down_matrix * &distance_left_matrix * &distance_right_matrix;
+    matrix::max(&scores).expect("the forest is not empty")
+}
+
+pub fn sum_visible_trees() -> Result<()> {
+    let sum = Day8::part1(&Day8::parse(io::stdin().lock())?, &Params::default())?;
     println!("{}", sum);
+    Ok(())
 }
 
-pub fn max_visible_trees() {
-    let treemap = reshape_treemap_boundary(&get_treemap());
-    let treemap_matrix = matrix::from_2d(&treemap);
-    let distance_up_matrix = matrix::rot90(
-        &matrix::from_2d(&get_least_equal_distances(&matrix::rot90(
-            &treemap_matrix,
-            1,
-        ))),
-        3,
-    );
-    let distance_down_matrix = matrix::flipud(&matrix::rot90(
-        &matrix::from_2d(&get_least_equal_distances(&matrix::rot90(
-            &matrix::flipud(&treemap_matrix),
-            1,
-        ))),
-        3,
-    ));
-    let distance_left_matrix = matrix::from_2d(&get_least_equal_distances(&treemap_matrix));
-    let distance_right_matrix = matrix::fliplr(&matrix::from_2d(&get_least_equal_distances(
-        &matrix::fliplr(&treemap_matrix),
-    )));
-
-    let mut max_score = 1;
-    for i in 1..treemap_matrix.shape[0] - 1 {
-        for j in 1..treemap_matrix.shape[1] - 1 {
-            max_score = cmp::max(
-                max_score,
-                matrix::index(&distance_up_matrix, &[i as usize, j as usize])
-                    * matrix::index(&distance_down_matrix, &[i as usize, j as usize])
-                    * matrix::index(&distance_left_matrix, &[i as usize, j as usize])
-                    * matrix::index(&distance_right_matrix, &[i as usize, j as usize]),
+pub fn max_visible_trees() -> Result<()> {
+    let max_score = Day8::part2(&Day8::parse(io::stdin().lock())?, &Params::default())?;
+    println!("Max score: {}", max_score);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "30373
+25512
+65332
+33549
+35390
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day8::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day8::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(21)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day8::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day8::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(8)
+        );
+    }
+
+    #[test]
+    fn test_thin_forests() {
+        for (forest, visible) in [("12\n34\n", 4), ("5\n", 1), ("123\n", 3), ("1\n2\n3\n", 3)] {
+            let model = Day8::parse_str(forest).unwrap();
+            assert_eq!(
+                Day8::part1(&model, &Params::default()).unwrap(),
+                Answer::Number(visible)
+            );
+            assert_eq!(
+                Day8::part2(&model, &Params::default()).unwrap(),
+                Answer::Number(0)
             );
         }
     }
-    println!("Max score: {}", max_score);
 }
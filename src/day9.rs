@@ -1,72 +1,55 @@
+use crate::error;
+use crate::error::Error;
+use crate::error::Result;
+use crate::grid;
+use crate::grid::SparseGrid;
 use crate::matrix;
 use crate::matrix::Point;
-use std::cmp;
+use crate::params::Params;
+use crate::solution::numbered_lines;
+use crate::solution::Answer;
+use crate::solution::Solution;
 use std::io;
-use std::ops::Sub;
+use std::io::BufRead;
 use std::vec::Vec;
 
-impl<T: std::ops::Sub<Output = T>> Sub for Point<T> {
-    type Output = Self;
+pub struct Day9;
 
-    fn sub(self, other: Self) -> Self::Output {
-        Self {
-            x: self.x - other.x,
-            y: self.y - other.y,
-        }
-    }
+pub struct Motions {
+    actions: Vec<(matrix::Direction, i64)>,
 }
 
-fn get_actions() -> (Vec<(matrix::Direction, i32)>, Vec<Vec<bool>>, (i32, i32)) {
+fn get_actions(input: impl BufRead) -> Result<Motions> {
     let mut actions = Vec::new();
 
-    for line in io::stdin().lines() {
-        let line_str = line.expect("IO failed reading data");
-        let mut iter = line_str.as_str().split_whitespace().clone();
-        let dir = iter.next().expect("Got direction");
-        let moves = iter.next().expect("Got moves");
-
-        let move_dir = match dir {
-            "D" => matrix::Direction::Down,
-            "U" => matrix::Direction::Up,
-            "L" => matrix::Direction::Left,
-            "R" => matrix::Direction::Right,
-            _ => panic!("Unknown direction"),
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let mut iter = line_str.as_str().split_whitespace();
+        let (dir, moves) = match (iter.next(), iter.next(), iter.next()) {
+            (Some(dir), Some(moves), None) => (dir, moves),
+            _ => {
+                return Err(Error::malformed(
+                    line_no,
+                    &line_str,
+                    "expected `<direction> <moves>`",
+                ))
+            }
         };
-        let num_moves = moves.parse::<i32>().expect("Got number of moves");
-        actions.push((move_dir, num_moves));
-    }
-    let mut x = 0;
-    let mut y = 0;
-    let mut min_x = i32::MAX;
-    let mut max_x = i32::MIN;
-    let mut min_y = i32::MAX;
-    let mut max_y = i32::MIN;
-    for (dir, moves) in &actions {
-        match dir {
-            matrix::Direction::Down => y -= moves,
-            matrix::Direction::Up => y += moves,
-            matrix::Direction::Left => x -= moves,
-            matrix::Direction::Right => x += moves,
-        }
-        min_x = cmp::min(min_x, x);
-        max_x = cmp::max(max_x, x);
-        min_y = cmp::min(min_y, y);
-        max_y = cmp::max(max_y, y);
+
+        let move_dir = matrix::Direction::parse(dir)
+            .ok_or_else(|| Error::unexpected_token(line_no, &line_str, dir))?;
+        let num_moves = error::parse_token::<u32>(moves, line_no, &line_str)?;
+        actions.push((move_dir, num_moves as i64));
     }
-    let states = (0..(max_x - min_x + 1))
-        .map(|_| vec![false; (max_y - min_y + 1) as usize])
-        .collect();
-    return (actions, states, (min_x, min_y));
+    Ok(Motions { actions })
 }
 
-fn catch_up_to_head(tail: &Point<i32>, head: &Point<i32>) -> Point<i32> {
+fn catch_up_to_head(tail: &Point<i64>, head: &Point<i64>) -> Point<i64> {
     let mut result = tail.clone();
-    for (dx, dy) in matrix::DIRECTIONS_WITH_ADJACENCY_AND_DIAGONAL_AND_ORIGIN {
-        if tail.x + dx == head.x && tail.y + dy == head.y {
-            return result;
-        }
+    if tail == head || grid::around8(tail).any(|p| p == *head) {
+        return result;
     }
-    let diff = head.clone() - tail.clone();
+    let diff = head - tail;
     if diff.x == 0 {
         result.y += diff.y / 2;
     } else if diff.y == 0 {
@@ -84,64 +67,46 @@ fn catch_up_to_head(tail: &Point<i32>, head: &Point<i32>) -> Point<i32> {
     result
 }
 
-pub fn sum_tail_visited() {
-    let (actions, mut states, (min_x, min_y)) = get_actions();
-    let mut head = Point::<i32> {
-        x: 0 - min_x,
-        y: 0 - min_y,
-    };
+fn count_visited(states: &SparseGrid<bool>) -> i32 {
+    states.len() as i32
+}
+
+fn simulate_tail(motions: &Motions) -> i32 {
+    let mut states = SparseGrid::new();
+    let mut head = Point::<i64> { x: 0, y: 0 };
     let mut tail = head.clone();
-    states[tail.x as usize][tail.y as usize] = true;
+    states.insert(&tail, true);
 
-    for (dir, moves) in &actions {
+    for (dir, moves) in &motions.actions {
         // println!("{:?}->{} = {:?}", dir, moves, states);
         for _ in 0..*moves {
-            match dir {
-                matrix::Direction::Down => {
-                    head.y -= 1;
-                    tail = catch_up_to_head(&tail, &head);
-                }
-                matrix::Direction::Up => {
-                    head.y += 1;
-                    tail = catch_up_to_head(&tail, &head);
-                }
-                matrix::Direction::Left => {
-                    head.x -= 1;
-                    tail = catch_up_to_head(&tail, &head);
-                }
-                matrix::Direction::Right => {
-                    head.x += 1;
-                    tail = catch_up_to_head(&tail, &head);
-                }
-            }
-            states[tail.x as usize][tail.y as usize] = true;
+            head += dir.delta();
+            tail = catch_up_to_head(&tail, &head);
+            states.insert(&tail, true);
         }
     }
 
-    let all_positions = states
-        .iter()
-        .map(|state| state.iter().fold(0, |sum, acc| sum + *acc as i32))
-        .fold(0, |sum, acc| sum + acc);
-    println!("{:#?}", all_positions);
+    count_visited(&states)
 }
 
 const NUM_TAILS: usize = 9;
 
-fn print_map(states: &Vec<Vec<bool>>, head: &Point<i32>, tails: &Vec<Point<i32>>) {
-    let mut current: Vec<Vec<char>> = states
-        .iter()
-        .map(|sliced| sliced.iter().map(|_| '.').collect())
-        .collect();
+#[allow(dead_code, unused_imports)]
+fn print_map(states: &SparseGrid<bool>, head: &Point<i64>, tails: &[Point<i64>]) {
+    let mut current = SparseGrid::new();
+    for (visited, _) in states.iter() {
+        current.insert(&visited, '.');
+    }
     for i in (0..tails.len()).rev() {
-        let tail = tails[i].clone();
-        current[tail.x as usize][tail.y as usize] =
-            char::from_u32((i + 1) as u32 + '0' as u32).expect("Character conversion");
+        current.insert(
+            &tails[i],
+            char::from_u32((i + 1) as u32 + '0' as u32).expect("Character conversion"),
+        );
     }
-    current[head.x as usize][head.y as usize] = 'H';
-    let mut m = matrix::from_2d(&current);
-    m = matrix::transpose(&m);
+    current.insert(head, 'H');
+    let (m, _) = current.to_matrix('.');
 
-    for i in (0..m.shape[0]).rev() {
+    for i in 0..m.shape[0] {
         let line = (0..m.shape[1])
             .map(|j| matrix::index(&m, &[i as usize, j as usize]))
             .collect::<String>();
@@ -149,81 +114,118 @@ fn print_map(states: &Vec<Vec<bool>>, head: &Point<i32>, tails: &Vec<Point<i32>>
     }
 }
 
-pub fn sum_last_tail_visited() {
-    let (actions, mut states, (min_x, min_y)) = get_actions();
-    let mut head = Point::<i32> {
-        x: 0 - min_x,
-        y: 0 - min_y,
-    };
-    let mut tails: Vec<Point<i32>> = (0..NUM_TAILS).map(|_| head.clone()).collect();
-    states[head.x as usize][head.y as usize] = true;
+fn simulate_tails(motions: &Motions, num_tails: usize) -> i32 {
+    let mut states = SparseGrid::new();
+    let mut head = Point::<i64> { x: 0, y: 0 };
+    let mut tails: Vec<Point<i64>> = (0..num_tails).map(|_| head.clone()).collect();
+    states.insert(&head, true);
 
-    for (dir, moves) in &actions {
+    for (dir, moves) in &motions.actions {
         // println!("{:?}->{} = {:?}", dir, moves, states);
-        println!("== {:?} {} ==", dir, *moves);
+        // println!("== {:?} {} ==", dir, *moves);
         for _ in 0..*moves {
-            let mut last_head = NUM_TAILS;
-            match dir {
-                matrix::Direction::Down => {
-                    head.y -= 1;
-                    for i in 0..tails.len() {
-                        if last_head == NUM_TAILS {
-                            tails[i] = catch_up_to_head(&tails[i], &head);
-                            last_head = 0;
-                        } else {
-                            tails[i] = catch_up_to_head(&tails[i], &tails[last_head]);
-                            last_head += 1;
-                        }
-                    }
-                }
-                matrix::Direction::Up => {
-                    head.y += 1;
-                    for i in 0..tails.len() {
-                        if last_head == NUM_TAILS {
-                            tails[i] = catch_up_to_head(&tails[i], &head);
-                            last_head = 0;
-                        } else {
-                            tails[i] = catch_up_to_head(&tails[i], &tails[last_head]);
-                            last_head += 1;
-                        }
-                    }
-                }
-                matrix::Direction::Left => {
-                    head.x -= 1;
-                    for i in 0..tails.len() {
-                        if last_head == NUM_TAILS {
-                            tails[i] = catch_up_to_head(&tails[i], &head);
-                            last_head = 0;
-                        } else {
-                            tails[i] = catch_up_to_head(&tails[i], &tails[last_head]);
-                            last_head += 1;
-                        }
-                    }
-                }
-                matrix::Direction::Right => {
-                    head.x += 1;
-                    for i in 0..tails.len() {
-                        if last_head == NUM_TAILS {
-                            tails[i] = catch_up_to_head(&tails[i], &head);
-                            last_head = 0;
-                        } else {
-                            tails[i] = catch_up_to_head(&tails[i], &tails[last_head]);
-                            last_head += 1;
-                        }
-                    }
+            let mut last_head = num_tails;
+            head += dir.delta();
+            for i in 0..tails.len() {
+                if last_head == num_tails {
+                    tails[i] = catch_up_to_head(&tails[i], &head);
+                    last_head = 0;
+                } else {
+                    tails[i] = catch_up_to_head(&tails[i], &tails[last_head]);
+                    last_head += 1;
                 }
             }
-            states[tails[NUM_TAILS - 1].x as usize][tails[NUM_TAILS - 1].y as usize] = true;
+            states.insert(&tails[num_tails - 1], true);
             // print_map(&states, &head, &tails);
             // println!("");
         }
-        print_map(&states, &head, &tails);
-        println!("");
+        // print_map(&states, &head, &tails);
+        // println!();
     }
 
-    let all_positions = states
-        .iter()
-        .map(|state| state.iter().fold(0, |sum, acc| sum + *acc as i32))
-        .fold(0, |sum, acc| sum + acc);
-    println!("{:#?}", all_positions);
+    count_visited(&states)
+}
+
+impl Solution for Day9 {
+    type Model = Motions;
+
+    const PARAMS: &'static [&'static str] = &["num_tails"];
+
+    fn parse(input: impl BufRead) -> Result<Self::Model> {
+        get_actions(input)
+    }
+
+    fn part1(motions: &Self::Model, _params: &Params) -> Result<Answer> {
+        Ok(simulate_tail(motions).into())
+    }
+
+    fn part2(motions: &Self::Model, params: &Params) -> Result<Answer> {
+        let num_tails = params.get("num_tails", NUM_TAILS)?;
+        if num_tails == 0 {
+            return Err(Error::invalid_state(
+                None,
+                "the rope needs at least one tail",
+            ));
+        }
+        Ok(simulate_tails(motions, num_tails).into())
+    }
+}
+
+pub fn sum_tail_visited() -> Result<()> {
+    let all_positions = Day9::part1(&Day9::parse(io::stdin().lock())?, &Params::default())?;
+    println!("{}", all_positions);
+    Ok(())
+}
+
+pub fn sum_last_tail_visited() -> Result<()> {
+    let all_positions = Day9::part2(&Day9::parse(io::stdin().lock())?, &Params::default())?;
+    println!("{}", all_positions);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2
+";
+    const LARGER_EXAMPLE: &str = "R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20
+";
+
+    #[test]
+    fn test_part1_example() {
+        let model = Day9::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day9::part1(&model, &Params::default()).unwrap(),
+            Answer::Number(13)
+        );
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let model = Day9::parse_str(EXAMPLE).unwrap();
+        assert_eq!(
+            Day9::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(1)
+        );
+        let model = Day9::parse_str(LARGER_EXAMPLE).unwrap();
+        assert_eq!(
+            Day9::part2(&model, &Params::default()).unwrap(),
+            Answer::Number(36)
+        );
+    }
 }
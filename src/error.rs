@@ -0,0 +1,159 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A line that does not have the expected layout at all.
+    MalformedLine {
+        line: usize,
+        text: String,
+        reason: String,
+    },
+    /// A line with the right layout but a token that cannot be used.
+    UnexpectedToken {
+        line: usize,
+        column: usize,
+        text: String,
+        token: String,
+    },
+    /// Input that parsed fine but cannot be solved, e.g. moving a crate off an empty stack.
+    InvalidState {
+        line: Option<usize>,
+        reason: String,
+    },
+    /// Matrix indices outside its shape.
+    OutOfBounds {
+        indices: Vec<usize>,
+        shape: Vec<usize>,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn malformed(line: usize, text: &str, reason: &str) -> Self {
+        Error::MalformedLine {
+            line,
+            text: text.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn unexpected_token(line: usize, text: &str, token: &str) -> Self {
+        Error::UnexpectedToken {
+            line,
+            column: column_of(text, token),
+            text: text.to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    pub fn invalid_state(line: Option<usize>, reason: &str) -> Self {
+        Error::InvalidState {
+            line,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn out_of_bounds(indices: &[usize], shape: &[usize]) -> Self {
+        Error::OutOfBounds {
+            indices: indices.to_vec(),
+            shape: shape.to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "failed reading input: {}", err),
+            Error::MalformedLine { line, text, reason } => {
+                write!(f, "line {}: {}: {:?}", line, reason, text)
+            }
+            Error::UnexpectedToken {
+                line,
+                column,
+                text,
+                token,
+            } => write!(
+                f,
+                "line {}, column {}: unexpected {:?} in {:?}",
+                line, column, token, text
+            ),
+            Error::InvalidState {
+                line: Some(line),
+                reason,
+            } => write!(f, "line {}: {}", line, reason),
+            Error::InvalidState { line: None, reason } => write!(f, "{}", reason),
+            Error::OutOfBounds { indices, shape } => {
+                write!(
+                    f,
+                    "index {:?} is out of bounds for shape {:?}",
+                    indices, shape
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+// 1-based column of `token` inside `text`; tokens are usually subslices of the line.
+fn column_of(text: &str, token: &str) -> usize {
+    let offset = (token.as_ptr() as usize).wrapping_sub(text.as_ptr() as usize);
+    match text.get(offset..offset.wrapping_add(token.len())) {
+        Some(sub) if sub == token => offset + 1,
+        _ => text.find(token).map_or(1, |i| i + 1),
+    }
+}
+
+/// Parses `token`, a piece of input line `line` reading `text`.
+pub fn parse_token<T: FromStr>(token: &str, line: usize, text: &str) -> Result<T> {
+    token
+        .parse::<T>()
+        .map_err(|_| Error::unexpected_token(line, text, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_token_reports_column() {
+        let text = "move 1 from x to 3";
+        let token = text.split_whitespace().nth(3).unwrap();
+        match parse_token::<usize>(token, 7, text) {
+            Err(Error::UnexpectedToken { line, column, .. }) => {
+                assert_eq!(line, 7);
+                assert_eq!(column, 13);
+            }
+            _ => panic!("expected an unexpected token error"),
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let err = Error::malformed(3, "R", "missing number of moves");
+        assert_eq!(err.to_string(), "line 3: missing number of moves: \"R\"");
+        let err = Error::unexpected_token(2, "X 4", "X");
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 1: unexpected \"X\" in \"X 4\""
+        );
+    }
+}
@@ -0,0 +1,644 @@
+use crate::matrix;
+use crate::matrix::Matrix;
+use crate::matrix::Point;
+use std::cmp;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use std::hash::Hasher;
+use std::ops;
+
+const OFFSETS4: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const OFFSETS8: [(i64, i64); 8] = [
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// What a point outside the grid refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Nothing; such points are skipped.
+    Bounded,
+    /// The point on the opposite side, as on a torus.
+    Wrapping,
+    /// The nearest point on the border.
+    Clamped,
+}
+
+/// A 2-d `Matrix` addressed by `Point`s, `x` along the rows and `y` along the columns.
+#[derive(Clone, Debug)]
+pub struct Grid<T> {
+    pub cells: Matrix<T>,
+    pub edge: Edge,
+}
+
+/// The 4 points sharing a side with `point`, regardless of any grid.
+pub fn around4(point: &Point<i64>) -> impl Iterator<Item = Point<i64>> {
+    let (x, y) = (point.x, point.y);
+    OFFSETS4.iter().map(move |(dx, dy)| Point {
+        x: x + dx,
+        y: y + dy,
+    })
+}
+
+/// The 8 points sharing a side or a corner with `point`, regardless of any grid.
+pub fn around8(point: &Point<i64>) -> impl Iterator<Item = Point<i64>> {
+    let (x, y) = (point.x, point.y);
+    OFFSETS8.iter().map(move |(dx, dy)| Point {
+        x: x + dx,
+        y: y + dy,
+    })
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(cells: Matrix<T>) -> Self {
+        Grid::with_edge(cells, Edge::Bounded)
+    }
+
+    pub fn with_edge(cells: Matrix<T>, edge: Edge) -> Self {
+        assert_eq!(cells.shape.len(), 2, "a grid has exactly 2 dimensions");
+        Grid { cells, edge }
+    }
+
+    pub fn from_rows(rows: &[Vec<T>]) -> Self {
+        Grid::new(matrix::from_2d(rows))
+    }
+
+    pub fn filled(rows: usize, columns: usize, value: T) -> Self {
+        Grid::new(matrix::from_nd(
+            vec![value; rows * columns],
+            &[rows, columns],
+        ))
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.shape[0] as usize
+    }
+
+    pub fn columns(&self) -> usize {
+        self.cells.shape[1] as usize
+    }
+
+    pub fn contains(&self, point: &Point<i64>) -> bool {
+        (0..self.rows() as i64).contains(&point.x) && (0..self.columns() as i64).contains(&point.y)
+    }
+
+    /// The point inside the grid that `point` refers to under the grid's `Edge`.
+    pub fn resolve(&self, point: &Point<i64>) -> Option<Point<i64>> {
+        let (rows, columns) = (self.rows() as i64, self.columns() as i64);
+        match self.edge {
+            _ if self.contains(point) => Some(point.clone()),
+            Edge::Bounded => None,
+            _ if rows == 0 || columns == 0 => None,
+            Edge::Wrapping => Some(Point {
+                x: point.x.rem_euclid(rows),
+                y: point.y.rem_euclid(columns),
+            }),
+            Edge::Clamped => Some(Point {
+                x: point.x.clamp(0, rows - 1),
+                y: point.y.clamp(0, columns - 1),
+            }),
+        }
+    }
+
+    pub fn get(&self, point: &Point<i64>) -> Option<&T> {
+        if !self.contains(point) {
+            return self.get(&self.resolve(point)?);
+        }
+        Some(matrix::get(
+            &self.cells,
+            &[point.x as usize, point.y as usize],
+        ))
+    }
+
+    pub fn get_mut(&mut self, point: &Point<i64>) -> Option<&mut T> {
+        if !self.contains(point) {
+            let point = self.resolve(point)?;
+            return self.get_mut(&point);
+        }
+        Some(matrix::get_mut(
+            &mut self.cells,
+            &[point.x as usize, point.y as usize],
+        ))
+    }
+
+    /// Panics when `point` is outside a bounded grid.
+    pub fn set(&mut self, point: &Point<i64>, value: T) {
+        *self.get_mut(point).expect("the point is outside the grid") = value;
+    }
+
+    /// Every point of the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point<i64>> {
+        matrix::indices(&self.cells).map(|indices| Point {
+            x: indices[0] as i64,
+            y: indices[1] as i64,
+        })
+    }
+
+    // the distinct resolved neighbours, leaving out any that fall back onto `point`.
+    fn resolve_all<'a>(
+        &'a self,
+        point: &Point<i64>,
+        offsets: &'static [(i64, i64)],
+    ) -> impl Iterator<Item = Point<i64>> + 'a {
+        let point = point.clone();
+        let resolve_offset = move |(dx, dy): &(i64, i64)| {
+            self.resolve(&Point {
+                x: point.x + dx,
+                y: point.y + dy,
+            })
+        };
+        (0..offsets.len()).filter_map(move |i| {
+            let next = resolve_offset(&offsets[i])?;
+            // only a wrapped or clamped point can repeat an earlier one.
+            let repeated = next == point
+                || (self.edge != Edge::Bounded
+                    && offsets[..i]
+                        .iter()
+                        .any(|offset| resolve_offset(offset).as_ref() == Some(&next)));
+            (!repeated).then_some(next)
+        })
+    }
+
+    pub fn neighbors4(&self, point: &Point<i64>) -> impl Iterator<Item = Point<i64>> + '_ {
+        self.resolve_all(point, &OFFSETS4)
+    }
+
+    pub fn neighbors8(&self, point: &Point<i64>) -> impl Iterator<Item = Point<i64>> + '_ {
+        self.resolve_all(point, &OFFSETS8)
+    }
+}
+
+impl<T: Clone> ops::Index<&Point<i64>> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: &Point<i64>) -> &T {
+        self.get(point).expect("the point is outside the grid")
+    }
+}
+
+impl<T: Clone> ops::IndexMut<&Point<i64>> for Grid<T> {
+    fn index_mut(&mut self, point: &Point<i64>) -> &mut T {
+        self.get_mut(point).expect("the point is outside the grid")
+    }
+}
+
+const CHUNK_SIZE: i64 = 16;
+
+// a multiplicative hash; chunk coordinates need no protection from collisions.
+#[derive(Default)]
+struct ChunkHasher(u64);
+
+impl Hasher for ChunkHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u64(b as u64);
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 = (self.0.rotate_left(5) ^ i).wrapping_mul(0x517cc1b727220a95);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Cells set anywhere on an unbounded plane, stored in square chunks so that
+/// nearby cells share one lookup.
+#[derive(Clone, Debug)]
+pub struct SparseGrid<T> {
+    chunks: HashMap<Point<i64>, Vec<Option<T>>, BuildHasherDefault<ChunkHasher>>,
+    len: usize,
+    bounds: Option<(Point<i64>, Point<i64>)>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid {
+            chunks: HashMap::default(),
+            len: 0,
+            bounds: None,
+        }
+    }
+}
+
+// the chunk holding `point` and the slot within it.
+fn locate(point: &Point<i64>) -> (Point<i64>, usize) {
+    let chunk = Point {
+        x: point.x.div_euclid(CHUNK_SIZE),
+        y: point.y.div_euclid(CHUNK_SIZE),
+    };
+    let slot = point.x.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + point.y.rem_euclid(CHUNK_SIZE);
+    (chunk, slot as usize)
+}
+
+impl<T: Clone> SparseGrid<T> {
+    pub fn new() -> Self {
+        SparseGrid::default()
+    }
+
+    /// The cells of `matrix` that `keep` accepts, with index `[0, 0]` at `origin`.
+    pub fn from_matrix(matrix: &Matrix<T>, origin: &Point<i64>, keep: impl Fn(&T) -> bool) -> Self {
+        let mut grid = SparseGrid::new();
+        for (indices, cell) in matrix::indexed_iter(matrix) {
+            if keep(cell) {
+                let point = Point {
+                    x: origin.x + indices[0] as i64,
+                    y: origin.y + indices[1] as i64,
+                };
+                grid.insert(&point, cell.clone());
+            }
+        }
+        grid
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The smallest and largest coordinates set so far, or `None` if nothing was.
+    pub fn bounds(&self) -> Option<(Point<i64>, Point<i64>)> {
+        self.bounds.clone()
+    }
+
+    pub fn get(&self, point: &Point<i64>) -> Option<&T> {
+        let (chunk, slot) = locate(point);
+        self.chunks.get(&chunk)?[slot].as_ref()
+    }
+
+    pub fn contains(&self, point: &Point<i64>) -> bool {
+        self.get(point).is_some()
+    }
+
+    /// Sets the cell at `point`, returning what was there before.
+    pub fn insert(&mut self, point: &Point<i64>, value: T) -> Option<T> {
+        let (chunk, slot) = locate(point);
+        let cells = self
+            .chunks
+            .entry(chunk)
+            .or_insert_with(|| vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize]);
+        let old = cells[slot].replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        self.bounds = Some(match self.bounds.take() {
+            None => (point.clone(), point.clone()),
+            Some((min, max)) => (
+                Point {
+                    x: cmp::min(min.x, point.x),
+                    y: cmp::min(min.y, point.y),
+                },
+                Point {
+                    x: cmp::max(max.x, point.x),
+                    y: cmp::max(max.y, point.y),
+                },
+            ),
+        });
+        old
+    }
+
+    /// Every set cell, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point<i64>, &T)> {
+        self.chunks.iter().flat_map(|(chunk, cells)| {
+            cells.iter().enumerate().filter_map(move |(slot, cell)| {
+                let point = Point {
+                    x: chunk.x * CHUNK_SIZE + slot as i64 / CHUNK_SIZE,
+                    y: chunk.y * CHUNK_SIZE + slot as i64 % CHUNK_SIZE,
+                };
+                cell.as_ref().map(|cell| (point, cell))
+            })
+        })
+    }
+
+    /// A dense copy of the bounding box with `fill` in unset cells, and the
+    /// point that index `[0, 0]` stands for.
+    pub fn to_matrix(&self, fill: T) -> (Matrix<T>, Point<i64>) {
+        let Some((min, max)) = self.bounds() else {
+            return (matrix::from_nd(Vec::new(), &[0, 0]), Point { x: 0, y: 0 });
+        };
+        let mut dense = Grid::filled(
+            (max.x - min.x + 1) as usize,
+            (max.y - min.y + 1) as usize,
+            fill,
+        );
+        for (point, cell) in self.iter() {
+            dense.set(
+                &Point {
+                    x: point.x - min.x,
+                    y: point.y - min.y,
+                },
+                cell.clone(),
+            );
+        }
+        (dense.cells, min)
+    }
+}
+
+const WORD_BITS: usize = 64;
+
+/// A dense grid of booleans packed 64 to a word. Each row starts on a fresh
+/// word, with column `y` at bit `y % 64` of word `y / 64`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitGrid {
+    rows: usize,
+    columns: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+// the bits of one word that fall in `start..end`.
+fn word_mask(start: usize, end: usize) -> u64 {
+    let high = if end == WORD_BITS {
+        u64::MAX
+    } else {
+        (1 << end) - 1
+    };
+    high & !((1 << start) - 1)
+}
+
+impl BitGrid {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        let words_per_row = columns.div_ceil(WORD_BITS);
+        BitGrid {
+            rows,
+            columns,
+            words_per_row,
+            words: vec![0; rows * words_per_row],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Appends a row with every bit clear.
+    pub fn push_row(&mut self) {
+        self.rows += 1;
+        self.words.resize(self.rows * self.words_per_row, 0);
+    }
+
+    fn locate(&self, point: &Point<i64>) -> Option<(usize, u64)> {
+        if !(0..self.rows as i64).contains(&point.x) || !(0..self.columns as i64).contains(&point.y)
+        {
+            return None;
+        }
+        let (x, y) = (point.x as usize, point.y as usize);
+        Some((x * self.words_per_row + y / WORD_BITS, 1 << (y % WORD_BITS)))
+    }
+
+    /// `None` outside the grid.
+    pub fn get(&self, point: &Point<i64>) -> Option<bool> {
+        let (word, bit) = self.locate(point)?;
+        Some(self.words[word] & bit != 0)
+    }
+
+    /// Panics when `point` is outside the grid.
+    pub fn set(&mut self, point: &Point<i64>, value: bool) {
+        let (word, bit) = self.locate(point).expect("the point is outside the grid");
+        if value {
+            self.words[word] |= bit;
+        } else {
+            self.words[word] &= !bit;
+        }
+    }
+
+    /// Sets the bit at `point`, returning whether it was clear before.
+    pub fn insert(&mut self, point: &Point<i64>) -> bool {
+        let (word, bit) = self.locate(point).expect("the point is outside the grid");
+        let was_clear = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        was_clear
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The packed words of row `x`.
+    pub fn row(&self, x: usize) -> &[u64] {
+        &self.words[x * self.words_per_row..(x + 1) * self.words_per_row]
+    }
+
+    fn row_mut(&mut self, x: usize) -> &mut [u64] {
+        &mut self.words[x * self.words_per_row..(x + 1) * self.words_per_row]
+    }
+
+    // bits beyond the last column are never set.
+    fn clear_padding(&mut self, x: usize) {
+        let last_bits = self.columns % WORD_BITS;
+        if last_bits != 0 {
+            if let Some(last) = self.row_mut(x).last_mut() {
+                *last &= word_mask(0, last_bits);
+            }
+        }
+    }
+
+    /// ORs packed `bits` into row `x`; missing words count as clear.
+    pub fn or_row(&mut self, x: usize, bits: &[u64]) {
+        for (word, bits) in self.row_mut(x).iter_mut().zip(bits) {
+            *word |= bits;
+        }
+        self.clear_padding(x);
+    }
+
+    /// ANDs packed `bits` into row `x`; missing words count as clear.
+    pub fn and_row(&mut self, x: usize, bits: &[u64]) {
+        let mut bits = bits.iter();
+        for word in self.row_mut(x) {
+            *word &= bits.next().copied().unwrap_or(0);
+        }
+    }
+
+    /// Whether row `x` and packed `bits` have a set bit in common.
+    pub fn row_intersects(&self, x: usize, bits: &[u64]) -> bool {
+        self.row(x)
+            .iter()
+            .zip(bits)
+            .any(|(word, bits)| word & bits != 0)
+    }
+
+    /// Moves row `x` by `n` columns, towards higher columns when positive;
+    /// bits pushed past either edge are lost.
+    pub fn shift_row(&mut self, x: usize, n: i64) {
+        let row = self.row_mut(x);
+        let old = row.to_vec();
+        let (word_shift, bit_shift) = (
+            (n.unsigned_abs() as usize) / WORD_BITS,
+            n.unsigned_abs() as usize % WORD_BITS,
+        );
+        for (i, word) in row.iter_mut().enumerate() {
+            *word = if n >= 0 {
+                let low = i.checked_sub(word_shift).map_or(0, |j| old[j] << bit_shift);
+                let carry = match i.checked_sub(word_shift + 1) {
+                    Some(j) if bit_shift > 0 => old[j] >> (WORD_BITS - bit_shift),
+                    _ => 0,
+                };
+                low | carry
+            } else {
+                let high = old.get(i + word_shift).map_or(0, |word| word >> bit_shift);
+                let carry = match old.get(i + word_shift + 1) {
+                    Some(word) if bit_shift > 0 => word << (WORD_BITS - bit_shift),
+                    _ => 0,
+                };
+                high | carry
+            };
+        }
+        self.clear_padding(x);
+    }
+
+    /// Whether any bit of row `x` is set in the columns `range`.
+    pub fn any_in_row(&self, x: usize, range: ops::Range<usize>) -> bool {
+        let end = cmp::min(range.end, self.columns);
+        if range.start >= end {
+            return false;
+        }
+        let row = self.row(x);
+        let (first, last) = (range.start / WORD_BITS, (end - 1) / WORD_BITS);
+        (first..=last).any(|i| {
+            let start = if i == first {
+                range.start % WORD_BITS
+            } else {
+                0
+            };
+            let stop = if i == last {
+                (end - 1) % WORD_BITS + 1
+            } else {
+                WORD_BITS
+            };
+            row[i] & word_mask(start, stop) != 0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(edge: Edge) -> Grid<i32> {
+        Grid::with_edge(
+            matrix::from_2d(&[vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]),
+            edge,
+        )
+    }
+
+    #[test]
+    fn test_get() {
+        let bounded = sample(Edge::Bounded);
+        assert_eq!(bounded.get(&Point { x: 1, y: 2 }), Some(&6));
+        assert_eq!(bounded.get(&Point { x: -1, y: 0 }), None);
+        assert_eq!(bounded.get(&Point { x: 0, y: 3 }), None);
+        let wrapping = sample(Edge::Wrapping);
+        assert_eq!(wrapping.get(&Point { x: -1, y: 4 }), Some(&8));
+        let clamped = sample(Edge::Clamped);
+        assert_eq!(clamped.get(&Point { x: -5, y: 4 }), Some(&3));
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let corner = Point { x: 0, y: 0 };
+        let bounded = sample(Edge::Bounded);
+        assert_eq!(bounded.neighbors4(&corner).count(), 2);
+        assert_eq!(bounded.neighbors8(&corner).count(), 3);
+        assert_eq!(bounded.neighbors8(&Point { x: 1, y: 1 }).count(), 8);
+        let wrapping = sample(Edge::Wrapping);
+        let values: Vec<i32> = wrapping.neighbors4(&corner).map(|p| wrapping[&p]).collect();
+        assert_eq!(values, vec![4, 2, 7, 3]);
+        let clamped = sample(Edge::Clamped);
+        assert_eq!(clamped.neighbors8(&corner).count(), 3);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut grid = Grid::filled(2, 3, false);
+        grid.set(&Point { x: 1, y: 2 }, true);
+        grid[&Point { x: 0, y: 0 }] = true;
+        let set: Vec<Point<i64>> = grid.points().filter(|p| grid[p]).collect();
+        assert_eq!(set, vec![Point { x: 0, y: 0 }, Point { x: 1, y: 2 }]);
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        grid.insert(&Point { x: -20, y: 3 }, 'a');
+        grid.insert(&Point { x: 5, y: -1 }, 'b');
+        assert_eq!(grid.insert(&Point { x: 5, y: -1 }, 'c'), Some('b'));
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.get(&Point { x: -20, y: 3 }), Some(&'a'));
+        assert_eq!(grid.get(&Point { x: -20, y: 4 }), None);
+        assert_eq!(
+            grid.bounds(),
+            Some((Point { x: -20, y: -1 }, Point { x: 5, y: 3 }))
+        );
+
+        let (dense, origin) = grid.to_matrix('.');
+        assert_eq!(dense.shape, vec![26, 5]);
+        assert_eq!(origin, Point { x: -20, y: -1 });
+        assert_eq!(matrix::index(&dense, &[0, 4]), 'a');
+        assert_eq!(matrix::index(&dense, &[25, 0]), 'c');
+        let back = SparseGrid::from_matrix(&dense, &origin, |c| *c != '.');
+        let mut cells: Vec<(Point<i64>, char)> = back.iter().map(|(p, c)| (p, *c)).collect();
+        cells.sort_by_key(|(p, _)| (p.x, p.y));
+        assert_eq!(
+            cells,
+            vec![(Point { x: -20, y: 3 }, 'a'), (Point { x: 5, y: -1 }, 'c')]
+        );
+    }
+
+    #[test]
+    fn test_bit_grid() {
+        let mut grid = BitGrid::new(3, 100);
+        assert!(grid.insert(&Point { x: 1, y: 70 }));
+        assert!(!grid.insert(&Point { x: 1, y: 70 }));
+        grid.set(&Point { x: 0, y: 3 }, true);
+        assert_eq!(grid.get(&Point { x: 1, y: 70 }), Some(true));
+        assert_eq!(grid.get(&Point { x: 1, y: 71 }), Some(false));
+        assert_eq!(grid.get(&Point { x: 1, y: 100 }), None);
+        assert_eq!(grid.count_ones(), 2);
+
+        assert!(grid.any_in_row(1, 64..71));
+        assert!(!grid.any_in_row(1, 0..70));
+        assert!(!grid.any_in_row(1, 71..200));
+        assert!(grid.row_intersects(0, &[0b1000]));
+        assert!(!grid.row_intersects(0, &[0b0100, u64::MAX]));
+
+        grid.shift_row(1, 29);
+        assert_eq!(grid.get(&Point { x: 1, y: 99 }), Some(true));
+        grid.shift_row(1, 1);
+        assert_eq!(grid.count_ones(), 1);
+        grid.shift_row(0, 62);
+        assert_eq!(grid.get(&Point { x: 0, y: 65 }), Some(true));
+        grid.shift_row(0, -64);
+        assert_eq!(grid.get(&Point { x: 0, y: 1 }), Some(true));
+
+        grid.or_row(2, &[0b110, u64::MAX]);
+        assert_eq!(grid.count_ones(), 1 + 2 + 36);
+        grid.and_row(2, &[0b010]);
+        assert_eq!(grid.row(2), &[0b010, 0]);
+        grid.push_row();
+        assert_eq!(grid.rows(), 4);
+        assert_eq!(grid.row(3), &[0, 0]);
+    }
+}
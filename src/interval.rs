@@ -0,0 +1,179 @@
+use std::cmp;
+use std::iter;
+use std::ops;
+use std::ops::Range;
+use std::vec::Vec;
+
+/// A set of values stored as sorted, disjoint half-open ranges.
+///
+/// Touching ranges such as `1..3` and `3..5` are merged into `1..5`.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        // every range from `lo` to `hi` overlaps or touches the new one.
+        let lo = self.ranges.partition_point(|r| r.end < range.start);
+        let hi = self.ranges.partition_point(|r| r.start <= range.end);
+        let mut merged = range;
+        if lo < hi {
+            merged.start = cmp::min(merged.start, self.ranges[lo].start);
+            merged.end = cmp::max(merged.end, self.ranges[hi - 1].end);
+        }
+        self.ranges.splice(lo..hi, [merged]);
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        // every range from `lo` to `hi` shares at least one value with the removed one.
+        let lo = self.ranges.partition_point(|r| r.end <= range.start);
+        let hi = self.ranges.partition_point(|r| r.start < range.end);
+        if lo == hi {
+            return;
+        }
+        let left = self.ranges[lo].start..range.start;
+        let right = range.end..self.ranges[hi - 1].end;
+        let kept = [left, right].into_iter().filter(|r| !r.is_empty());
+        self.ranges.splice(lo..hi, kept);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= *value);
+        i < self.ranges.len() && self.ranges[i].start <= *value
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for range in &other.ranges {
+            result.insert(range.clone());
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let common = cmp::max(a.start, b.start)..cmp::min(a.end, b.end);
+            if !common.is_empty() {
+                ranges.push(common);
+            }
+            // whichever ends first cannot overlap anything further.
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for range in &other.ranges {
+            result.remove(range.clone());
+        }
+        result
+    }
+
+    /// The ranges within `within` not covered by the set, in order.
+    pub fn gaps(&self, within: Range<T>) -> impl Iterator<Item = Range<T>> {
+        IntervalSet::from(within)
+            .difference(self)
+            .ranges
+            .into_iter()
+    }
+
+    /// The number of values covered.
+    pub fn covered_len(&self) -> T
+    where
+        T: ops::Sub<Output = T> + iter::Sum,
+    {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+}
+
+impl<T: Copy + Ord> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(ranges: I) -> Self {
+        let mut set = Self::new();
+        for range in ranges {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut set: IntervalSet<i64> = [10..12, 0..2, 5..6].into_iter().collect();
+        assert_eq!(set.ranges(), &[0..2, 5..6, 10..12]);
+        set.insert(2..3);
+        set.insert(4..4);
+        assert_eq!(set.ranges(), &[0..3, 5..6, 10..12]);
+        set.insert(1..11);
+        assert_eq!(set, IntervalSet::from(0..12));
+        assert_eq!(set.covered_len(), 12);
+        assert!(set.contains(&0));
+        assert!(!set.contains(&12));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set = IntervalSet::from(0..10);
+        set.remove(3..5);
+        assert_eq!(set.ranges(), &[0..3, 5..10]);
+        set.remove(8..20);
+        set.remove(-5..1);
+        assert_eq!(set.ranges(), &[1..3, 5..8]);
+        set.remove(0..10);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: IntervalSet<i32> = [0..4, 6..10].into_iter().collect();
+        let b: IntervalSet<i32> = [2..7, 9..12].into_iter().collect();
+        assert_eq!(a.union(&b), IntervalSet::from(0..12));
+        assert_eq!(a.intersection(&b).ranges(), &[2..4, 6..7, 9..10]);
+        assert_eq!(a.difference(&b).ranges(), &[0..2, 7..9]);
+        assert!(a.intersection(&b).is_subset(&a));
+        assert!(!a.is_subset(&b));
+        assert_eq!(a.gaps(-2..8).collect::<Vec<_>>(), vec![-2..0, 4..6]);
+    }
+}
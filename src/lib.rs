@@ -1,3 +1,5 @@
+pub mod answers;
+pub mod bench;
 mod char_bins;
 pub mod day1;
 pub mod day10;
@@ -16,4 +18,12 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
-mod matrix;
+pub mod error;
+pub mod grid;
+pub mod interval;
+pub mod matrix;
+pub mod params;
+pub mod puzzles;
+pub mod report;
+pub mod search;
+pub mod solution;
@@ -1,5 +1,453 @@
 extern crate aoc2022;
 
+use aoc2022::answers;
+use aoc2022::bench;
+use aoc2022::bench::Baseline;
+use aoc2022::bench::Samples;
+use aoc2022::params::Params;
+use aoc2022::puzzles;
+use aoc2022::puzzles::Outcome;
+use aoc2022::puzzles::Puzzle;
+use aoc2022::report::Format;
+use aoc2022::report::Record;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+use std::time::Instant;
+
+const USAGE: &str = "usage:
+    aoc2022 run <day> <part> [--input FILE] [--format text|json] [--param KEY=VALUE]...
+    aoc2022 run --all [--inputs DIR] [--format text|json] [--param KEY=VALUE]...
+    aoc2022 bench <day> <part> [--input FILE] [--reps N] [--baseline FILE] [--save-baseline FILE]
+                  [--param KEY=VALUE]...
+    aoc2022 verify [--answers FILE]
+    aoc2022 list";
+
+const DEFAULT_REPS: usize = 10;
+const DEFAULT_ANSWERS: &str = "answers.txt";
+const DEFAULT_INPUTS: &str = "inputs";
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_number(arg: Option<&String>, what: &str) -> u32 {
+    let arg = arg.unwrap_or_else(|| usage_error(&format!("missing <{}>", what)));
+    arg.parse::<u32>()
+        .unwrap_or_else(|_| usage_error(&format!("<{}> must be a number, got {:?}", what, arg)))
+}
+
+fn find_puzzle(day: u32, part: u32) -> &'static Puzzle {
+    puzzles::find(day, part).unwrap_or_else(|| {
+        eprintln!("error: no solver for day {} part {}", day, part);
+        process::exit(1);
+    })
+}
+
+fn parse_param(params: &mut Params, arg: Option<&String>) {
+    let arg = arg.unwrap_or_else(|| usage_error("--param needs KEY=VALUE"));
+    params
+        .insert_assignment(arg)
+        .unwrap_or_else(|| usage_error(&format!("--param needs KEY=VALUE, got {:?}", arg)));
+}
+
+fn check_params(params: &Params, known: &[&str], what: &str) {
+    for key in params.keys() {
+        if !known.contains(&key) {
+            usage_error(&format!(
+                "{} has no parameter {:?} (known: {})",
+                what,
+                key,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ));
+        }
+    }
+}
+
+fn list() {
+    for puzzle in &puzzles::PUZZLES {
+        if puzzle.params.is_empty() {
+            println!(
+                "day {:>2} part {}  {}",
+                puzzle.day, puzzle.part, puzzle.name
+            );
+        } else {
+            println!(
+                "day {:>2} part {}  {}  [{}]",
+                puzzle.day,
+                puzzle.part,
+                puzzle.name,
+                puzzle.params.join(", ")
+            );
+        }
+    }
+}
+
+fn parse_format(arg: Option<&String>) -> Format {
+    let arg = arg.unwrap_or_else(|| usage_error("--format needs text or json"));
+    Format::from_name(arg)
+        .unwrap_or_else(|| usage_error(&format!("unknown format {:?}, use text or json", arg)))
+}
+
+fn run(args: &[String]) {
+    if args.first().map(|s| s.as_str()) == Some("--all") {
+        return run_all(&args[1..]);
+    }
+    let day = parse_number(args.first(), "day");
+    let part = parse_number(args.get(1), "part");
+    let mut input = None;
+    let mut format = Format::Text;
+    let mut params = Params::default();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--input" => {
+                input = Some(
+                    rest.next()
+                        .unwrap_or_else(|| usage_error("--input needs a file")),
+                );
+            }
+            "--format" => format = parse_format(rest.next()),
+            "--param" => parse_param(&mut params, rest.next()),
+            _ => usage_error(&format!("unexpected argument {:?}", arg)),
+        }
+    }
+
+    let puzzle = find_puzzle(day, part);
+    check_params(&params, puzzle.params, &format!("day {}", day));
+    let input = read_input(input);
+
+    let record = puzzle.solve_timed(&input, &params).unwrap_or_else(|err| {
+        eprintln!("error: day {} part {}: {}", day, part, err);
+        process::exit(1);
+    });
+    match format {
+        Format::Text => println!("{}", record.answer),
+        Format::Json => println!("{}", record.to_json()),
+    }
+}
+
+// multi-line answers only show their first line in the table.
+fn summary_answer(record: &Record) -> String {
+    let answer = record.answer.to_string();
+    let mut lines = answer.lines();
+    let first = lines.next().unwrap_or("").to_string();
+    match lines.count() {
+        0 => first,
+        more => format!("{} (+{} lines)", first, more),
+    }
+}
+
+fn run_all(args: &[String]) {
+    let mut dir = DEFAULT_INPUTS;
+    let mut format = Format::Text;
+    let mut params = Params::default();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--inputs" => {
+                dir = rest
+                    .next()
+                    .unwrap_or_else(|| usage_error("--inputs needs a directory"));
+            }
+            "--format" => format = parse_format(rest.next()),
+            "--param" => parse_param(&mut params, rest.next()),
+            _ => usage_error(&format!("unexpected argument {:?}", arg)),
+        }
+    }
+    let known: Vec<&str> = puzzles::PUZZLES
+        .iter()
+        .flat_map(|p| p.params.iter().copied())
+        .collect();
+    check_params(&params, &known, "no day");
+
+    let start = Instant::now();
+    let outcomes = puzzles::solve_all(Path::new(dir), &params);
+    let elapsed = start.elapsed();
+
+    let mut failed = 0;
+    if format == Format::Text {
+        println!("day part  {:>12}  {:<8} answer", "time", "status");
+    }
+    for (puzzle, outcome) in puzzles::PUZZLES.iter().zip(&outcomes) {
+        match (format, outcome) {
+            (Format::Json, Outcome::Solved(record)) => println!("{}", record.to_json()),
+            (Format::Text, Outcome::Solved(record)) => println!(
+                "{:>3} {:>4}  {:>12?}  {:<8} {}",
+                puzzle.day,
+                puzzle.part,
+                record.elapsed,
+                "ok",
+                summary_answer(record)
+            ),
+            (Format::Text, Outcome::Missing(reason)) => println!(
+                "{:>3} {:>4}  {:>12}  {:<8} {}",
+                puzzle.day, puzzle.part, "-", "missing", reason
+            ),
+            (_, Outcome::Failed(reason)) => {
+                failed += 1;
+                match format {
+                    Format::Text => println!(
+                        "{:>3} {:>4}  {:>12}  {:<8} {}",
+                        puzzle.day, puzzle.part, "-", "failed", reason
+                    ),
+                    Format::Json => {
+                        eprintln!("error: day {} part {}: {}", puzzle.day, puzzle.part, reason)
+                    }
+                }
+            }
+            (Format::Json, Outcome::Missing(_)) => {}
+        }
+    }
+    if format == Format::Text {
+        let solved = outcomes
+            .iter()
+            .filter(|o| matches!(o, Outcome::Solved(_)))
+            .count();
+        println!(
+            "{} solved, {} failed, {} missing in {:?}",
+            solved,
+            failed,
+            outcomes.len() - solved - failed,
+            elapsed
+        );
+    }
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+fn read_input(path: Option<&String>) -> String {
+    let input = match path {
+        None => io::read_to_string(io::stdin()),
+        Some(path) => fs::read_to_string(path),
+    };
+    input.unwrap_or_else(|err| {
+        eprintln!(
+            "error: cannot read {}: {}",
+            path.map_or("stdin", |p| p.as_str()),
+            err
+        );
+        process::exit(1);
+    })
+}
+
+fn print_samples(label: &str, samples: &Samples) {
+    println!(
+        "    {:<6} min {:>12?}  median {:>12?}  max {:>12?}",
+        label,
+        samples.min(),
+        samples.median(),
+        samples.max()
+    );
+}
+
+// prints the change against the baseline median and whether it is a regression.
+fn compare_median(label: &str, baseline: Duration, current: Duration) -> bool {
+    let change = (current.as_secs_f64() / baseline.as_secs_f64() - 1.0) * 100.0;
+    let regression = bench::is_regression(baseline, current);
+    println!(
+        "    {:<6} median {:?} -> {:?} ({:+.1}%){}",
+        label,
+        baseline,
+        current,
+        change,
+        if regression { "  REGRESSION" } else { "" }
+    );
+    regression
+}
+
+fn run_bench(args: &[String]) {
+    let day = parse_number(args.first(), "day");
+    let part = parse_number(args.get(1), "part");
+    let mut input = None;
+    let mut reps = DEFAULT_REPS;
+    let mut baseline_path = None;
+    let mut save_path = None;
+    let mut params = Params::default();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--input" => {
+                input = Some(
+                    rest.next()
+                        .unwrap_or_else(|| usage_error("--input needs a file")),
+                );
+            }
+            "--reps" => {
+                reps = parse_number(rest.next(), "reps") as usize;
+                if reps == 0 {
+                    usage_error("<reps> must be at least 1");
+                }
+            }
+            "--baseline" => {
+                baseline_path = Some(
+                    rest.next()
+                        .unwrap_or_else(|| usage_error("--baseline needs a file")),
+                );
+            }
+            "--save-baseline" => {
+                save_path = Some(
+                    rest.next()
+                        .unwrap_or_else(|| usage_error("--save-baseline needs a file")),
+                );
+            }
+            "--param" => parse_param(&mut params, rest.next()),
+            _ => usage_error(&format!("unexpected argument {:?}", arg)),
+        }
+    }
+
+    let puzzle = find_puzzle(day, part);
+    check_params(&params, puzzle.params, &format!("day {}", day));
+    let input = read_input(input);
+    let result = (puzzle.bench)(&input, reps, &params).unwrap_or_else(|err| {
+        eprintln!("error: day {} part {}: {}", day, part, err);
+        process::exit(1);
+    });
+
+    println!(
+        "day {} part {}: {} ({} reps)",
+        day, part, result.answer, reps
+    );
+    print_samples("parse", &result.parse);
+    print_samples("solve", &result.solve);
+
+    let mut regression = false;
+    if let Some(path) = baseline_path {
+        let baseline = Baseline::load(path).unwrap_or_else(|err| {
+            eprintln!("error: baseline {}: {}", path, err);
+            process::exit(1);
+        });
+        match baseline.get(day, part) {
+            Some((parse, solve)) => {
+                println!("  vs {}:", path);
+                regression |= compare_median("parse", parse, result.parse.median());
+                regression |= compare_median("solve", solve, result.solve.median());
+            }
+            None => println!("  no baseline for day {} part {} in {}", day, part, path),
+        }
+    }
+
+    if let Some(path) = save_path {
+        let mut baseline = if Path::new(path).exists() {
+            Baseline::load(path).unwrap_or_else(|err| {
+                eprintln!("error: baseline {}: {}", path, err);
+                process::exit(1);
+            })
+        } else {
+            Baseline::default()
+        };
+        baseline.set(day, part, &result);
+        if let Err(err) = baseline.save(path) {
+            eprintln!("error: cannot write {}: {}", path, err);
+            process::exit(1);
+        }
+    }
+
+    if regression {
+        process::exit(1);
+    }
+}
+
+fn verify(args: &[String]) {
+    let mut path = DEFAULT_ANSWERS;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--answers" => {
+                path = rest
+                    .next()
+                    .unwrap_or_else(|| usage_error("--answers needs a file"));
+            }
+            _ => usage_error(&format!("unexpected argument {:?}", arg)),
+        }
+    }
+    let expected = answers::load(path).unwrap_or_else(|err| {
+        eprintln!("error: answers {}: {}", path, err);
+        process::exit(1);
+    });
+
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for entry in &expected {
+        let label = format!(
+            "day {:>2} part {} ({})",
+            entry.day,
+            entry.part,
+            entry.input.display()
+        );
+        let puzzle = match puzzles::find(entry.day, entry.part) {
+            Some(puzzle) => puzzle,
+            None => {
+                println!("FAIL    {}: no solver", label);
+                failed += 1;
+                continue;
+            }
+        };
+        let file = match File::open(&entry.input) {
+            Ok(file) => file,
+            Err(err) => {
+                println!("MISSING {}: {}", label, err);
+                missing += 1;
+                continue;
+            }
+        };
+        match (puzzle.solve)(&mut BufReader::new(file), &Params::default()) {
+            Ok(answer) if answer.to_string() == entry.answer => {
+                println!("PASS    {}", label);
+                passed += 1;
+            }
+            Ok(answer) => {
+                println!(
+                    "FAIL    {}: expected {:?}, got {:?}",
+                    label,
+                    entry.answer,
+                    answer.to_string()
+                );
+                failed += 1;
+            }
+            Err(err) => {
+                println!("FAIL    {}: {}", label, err);
+                failed += 1;
+            }
+        }
+    }
+    for puzzle in &puzzles::PUZZLES {
+        let answered = expected
+            .iter()
+            .any(|e| e.day == puzzle.day && e.part == puzzle.part);
+        if !answered {
+            println!(
+                "MISSING day {:>2} part {}: no answer in {}",
+                puzzle.day, puzzle.part, path
+            );
+            missing += 1;
+        }
+    }
+
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
 fn main() {
-    aoc2022::day17::simulate_long_tetris();
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("run") => run(&args[1..]),
+        Some("bench") => run_bench(&args[1..]),
+        Some("verify") => verify(&args[1..]),
+        Some("list") => list(),
+        Some(command) => usage_error(&format!("unknown command {:?}", command)),
+        None => usage_error("missing command"),
+    }
 }
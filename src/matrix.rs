@@ -1,7 +1,13 @@
-use std::cell::RefCell;
+use crate::error::Error;
+use crate::error::Result;
+use crate::solution::numbered_lines;
 use std::clone::Clone;
+use std::cmp;
 use std::fmt;
-use std::rc::Rc;
+use std::io::BufRead;
+use std::iter;
+use std::ops;
+use std::sync::Arc;
 use std::vec::Vec;
 
 #[allow(dead_code)]
@@ -33,7 +39,9 @@ pub const DIRECTIONS_WITH_ADJACENCY_AND_DIAGONAL_AND_ORIGIN: [(i32, i32); 9] = [
     (0, 0),
 ];
 
-#[derive(Debug)]
+/// A step on a grid where `x` is the row (growing downwards) and `y` the column
+/// (growing rightwards), as used by `Grid` and `from_chars`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Direction {
     Left,
     Right,
@@ -41,180 +49,1066 @@ pub enum Direction {
     Down,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// Accepts `U`/`D`/`L`/`R` as well as the arrows `^`/`v`/`<`/`>`.
+    pub fn parse(token: &str) -> Option<Self> {
+        match token {
+            "U" | "^" => Some(Direction::Up),
+            "D" | "v" => Some(Direction::Down),
+            "L" | "<" => Some(Direction::Left),
+            "R" | ">" => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    pub fn delta(&self) -> Point<i64> {
+        match self {
+            Direction::Up => Point { x: -1, y: 0 },
+            Direction::Down => Point { x: 1, y: 0 },
+            Direction::Left => Point { x: 0, y: -1 },
+            Direction::Right => Point { x: 0, y: 1 },
+        }
+    }
+
+    pub fn turn_left(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+
+    pub fn turn_right(&self) -> Self {
+        self.turn_left().opposite()
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Point<T> {
     pub x: T,
     pub y: T,
 }
 
-pub fn manhattan_distance_i64(this: &Point<i64>, other: &Point<i64>) -> i64 {
-    i64::abs(this.x - other.x) + i64::abs(this.y - other.y)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+macro_rules! impl_point_ops {
+    ($point:ident, $($field:ident),+) => {
+        impl<T: ops::Add<Output = T>> ops::Add for $point<T> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                $point { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl<T: Clone + ops::Add<Output = T>> ops::Add for &$point<T> {
+            type Output = $point<T>;
+
+            fn add(self, rhs: Self) -> $point<T> {
+                self.clone() + rhs.clone()
+            }
+        }
+
+        impl<T: Clone + ops::Add<Output = T>> ops::AddAssign for $point<T> {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = self.clone() + rhs;
+            }
+        }
+
+        impl<T: ops::Sub<Output = T>> ops::Sub for $point<T> {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                $point { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl<T: Clone + ops::Sub<Output = T>> ops::Sub for &$point<T> {
+            type Output = $point<T>;
+
+            fn sub(self, rhs: Self) -> $point<T> {
+                self.clone() - rhs.clone()
+            }
+        }
+
+        impl<T: Clone + ops::Sub<Output = T>> ops::SubAssign for $point<T> {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = self.clone() - rhs;
+            }
+        }
+
+        impl<T: Clone + ops::Mul<Output = T>> ops::Mul<T> for $point<T> {
+            type Output = Self;
+
+            fn mul(self, rhs: T) -> Self {
+                $point { $($field: self.$field * rhs.clone()),+ }
+            }
+        }
+
+        impl<T: ops::Neg<Output = T>> ops::Neg for $point<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                $point { $($field: -self.$field),+ }
+            }
+        }
+
+        impl<T: Coordinate> Metric for $point<T> {
+            type Scalar = T;
+
+            fn axis_distances(&self, other: &Self) -> impl Iterator<Item = T> {
+                [$(self.$field.distance(other.$field)),+].into_iter()
+            }
+
+            fn axis_distances_checked(&self, other: &Self) -> impl Iterator<Item = Option<T>> {
+                [$(self.$field.checked_distance(other.$field)),+].into_iter()
+            }
+        }
+    };
+}
+
+/// An integer usable as a point coordinate.
+pub trait Coordinate: Copy + Ord + iter::Sum + ops::Mul<Output = Self> {
+    const ZERO: Self;
+
+    /// The absolute difference, `|self - other|`.
+    fn distance(self, other: Self) -> Self;
+
+    /// Like `distance`, but `None` when the difference does not fit in `Self`.
+    fn checked_distance(self, other: Self) -> Option<Self>;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_coordinate {
+    ($($t:ty),+ => signed) => {
+        $(impl Coordinate for $t {
+            const ZERO: Self = 0;
+
+            fn distance(self, other: Self) -> Self {
+                (self - other).abs()
+            }
+
+            fn checked_distance(self, other: Self) -> Option<Self> {
+                self.checked_sub(other).and_then(<$t>::checked_abs)
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+        })+
+    };
+    ($($t:ty),+ => unsigned) => {
+        $(impl Coordinate for $t {
+            const ZERO: Self = 0;
+
+            fn distance(self, other: Self) -> Self {
+                self.abs_diff(other)
+            }
+
+            fn checked_distance(self, other: Self) -> Option<Self> {
+                Some(self.abs_diff(other))
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+        })+
+    };
+}
+
+impl_coordinate!(i8, i16, i32, i64, i128, isize => signed);
+impl_coordinate!(u8, u16, u32, u64, u128, usize => unsigned);
+
+/// A point whose per-axis distances to another point of the same kind are known.
+pub trait Metric {
+    type Scalar: Coordinate;
+
+    fn axis_distances(&self, other: &Self) -> impl Iterator<Item = Self::Scalar>;
+
+    fn axis_distances_checked(&self, other: &Self) -> impl Iterator<Item = Option<Self::Scalar>>;
+}
+
+impl_point_ops!(Point, x, y);
+impl_point_ops!(Point3, x, y, z);
+
+pub fn manhattan_distance<P: Metric>(this: &P, other: &P) -> P::Scalar {
+    this.axis_distances(other).sum()
+}
+
+/// Like `manhattan_distance`, but `None` when the distance does not fit in the scalar type.
+pub fn checked_manhattan_distance<P: Metric>(this: &P, other: &P) -> Option<P::Scalar> {
+    this.axis_distances_checked(other)
+        .try_fold(P::Scalar::ZERO, |sum, d| sum.checked_add(d?))
+}
+
+pub fn chebyshev_distance<P: Metric>(this: &P, other: &P) -> P::Scalar {
+    this.axis_distances(other).fold(P::Scalar::ZERO, cmp::max)
+}
+
+pub fn euclidean_distance_squared<P: Metric>(this: &P, other: &P) -> P::Scalar {
+    this.axis_distances(other).map(|d| d * d).sum()
 }
 
 #[derive(Clone, Debug)]
 #[allow(dead_code)]
 pub struct Matrix<T> {
-    data: Rc<RefCell<Vec<T>>>,
+    // shared by every view; `Arc` keeps views `Send`/`Sync` for worker threads.
+    data: Arc<Vec<T>>,
     start: usize,
     pub shape: Vec<i32>,
     pub strides: Vec<i32>, // not a byte stride but a unit given I have type here.
     back_strides: Vec<i32>,
 }
 
-impl<T: std::fmt::Display + Copy> fmt::Display for Matrix<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.shape.len() == 1 {
-            write!(f, "[").map_err(|err| println!("{:?}", err)).ok();
-            for i in 0..self.shape[0] {
-                write!(f, "{}", index(&self, &[i as usize]))
-                    .map_err(|err| println!("{:?}", err))
-                    .ok();
+// writes the sub-matrix at `indices`, nesting one bracket per remaining axis.
+fn fmt_axis<T: fmt::Display + Copy>(
+    matrix: &Matrix<T>,
+    indices: &mut Vec<usize>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let axis = indices.len();
+    write!(f, "[")?;
+    for i in 0..matrix.shape[axis] as usize {
+        indices.push(i);
+        if axis + 1 == matrix.shape.len() {
+            if i > 0 {
+                write!(f, ",")?;
             }
-            writeln!(f, "]")
-        } else if self.shape.len() == 2 {
-            write!(f, "[").map_err(|err| println!("{:?}", err)).ok();
-            for i in 0..self.shape[0] {
-                if i > 0 {
-                    write!(f, " ").map_err(|err| println!("{:?}", err)).ok();
-                }
-                write!(f, "[").map_err(|err| println!("{:?}", err)).ok();
-                for j in 0..self.shape[1] - 1 {
-                    write!(f, "{},", index(&self, &[i as usize, j as usize]))
-                        .map_err(|err| println!("{:?}", err))
-                        .ok();
-                }
-                writeln!(
-                    f,
-                    "{}],",
-                    index(&self, &[i as usize, (self.shape[1] - 1) as usize])
-                )
-                .map_err(|err| println!("{:?}", err))
-                .ok();
+            write!(f, "{}", index(matrix, indices))?;
+        } else {
+            if i > 0 {
+                write!(f, ",\n{}", " ".repeat(axis + 1))?;
             }
-            writeln!(f, "]")
+            fmt_axis(matrix, indices, f)?;
+        }
+        indices.pop();
+    }
+    write!(f, "]")
+}
+
+impl<T: fmt::Display + Copy> fmt::Display for Matrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.shape.is_empty() {
+            write!(f, "{}", index(self, &[]))
         } else {
-            write!(f, "")
+            fmt_axis(self, &mut Vec::new(), f)
         }
     }
 }
 
-fn get_back_strides(strides: &Vec<i32>, shape: &Vec<i32>) -> Vec<i32> {
-    return strides
+// a length as a dimension, which `Matrix` keeps as `i32`.
+fn to_dim(len: usize) -> i32 {
+    i32::try_from(len).unwrap_or_else(|_| panic!("{} is too long for a matrix dimension", len))
+}
+
+// the shape as lengths; dimensions are never negative.
+fn get_dims(shape: &[i32]) -> Vec<usize> {
+    shape.iter().map(|&dim| dim.max(0) as usize).collect()
+}
+
+fn get_back_strides(strides: &[i32], shape: &[i32]) -> Vec<i32> {
+    strides
         .iter()
         .zip(shape)
-        .map(|(&s, &dim)| s * (dim - 1))
-        .collect();
+        .map(|(&s, &dim)| {
+            s.checked_mul(dim - 1)
+                .unwrap_or_else(|| panic!("shape {:?} has too many elements", shape))
+        })
+        .collect()
 }
 
 #[allow(dead_code, unused_imports)]
-pub fn from_1d<T: Clone>(data: Rc<RefCell<Vec<T>>>) -> Matrix<T> {
-    return Matrix {
-        data: Rc::clone(&data),
+pub fn from_1d<T: Clone>(data: Vec<T>) -> Matrix<T> {
+    Matrix {
+        shape: vec![to_dim(data.len())],
+        data: Arc::new(data),
         start: 0,
-        shape: vec![(*data).borrow().len() as i32],
         strides: vec![1],
         back_strides: vec![1],
-    };
+    }
 }
 
-pub fn from_2d<T: Clone>(matrix: &Vec<Vec<T>>) -> Matrix<T> {
+// row-major strides for a freshly laid out `shape`.
+fn get_contiguous_strides(shape: &[i32]) -> Vec<i32> {
+    let mut strides = vec![1_i32; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1]
+            .checked_mul(shape[i + 1])
+            .unwrap_or_else(|| panic!("shape {:?} has too many elements", shape));
+    }
+    strides
+}
+
+pub fn from_2d<T: Clone>(matrix: &[Vec<T>]) -> Matrix<T> {
     let mut data = Vec::new();
     for row in matrix {
-        data.extend_from_slice(&row);
+        data.extend_from_slice(row);
     }
-    let shape = vec![matrix.len() as i32, matrix[0].len() as i32];
-    let strides = vec![matrix[0].len() as i32, 1];
+    let shape = vec![to_dim(matrix.len()), to_dim(matrix[0].len())];
+    let strides = get_contiguous_strides(&shape);
     let back_strides = get_back_strides(&strides, &shape);
-    return Matrix {
-        data: Rc::new(RefCell::new(data)),
+    Matrix {
+        data: Arc::new(data),
         start: 0,
         shape,
         strides,
         back_strides,
-    };
+    }
 }
 
-pub fn index<T: Clone + Copy>(matrix: &Matrix<T>, indices: &[usize]) -> T {
-    let index_num = matrix
+/// Lays `data` out row-major over `shape`.
+pub fn from_nd<T: Clone>(data: Vec<T>, shape: &[usize]) -> Matrix<T> {
+    assert_eq!(
+        data.len(),
+        shape.iter().product::<usize>(),
+        "{} elements cannot fill shape {:?}",
+        data.len(),
+        shape
+    );
+    let shape: Vec<i32> = shape.iter().map(|&dim| to_dim(dim)).collect();
+    let strides = get_contiguous_strides(&shape);
+    let back_strides = get_back_strides(&strides, &shape);
+    Matrix {
+        data: Arc::new(data),
+        start: 0,
+        shape,
+        strides,
+        back_strides,
+    }
+}
+
+pub fn zeros<T: Clone + Default>(shape: &[usize]) -> Matrix<T> {
+    from_nd(vec![T::default(); shape.iter().product()], shape)
+}
+
+/// Marker chars with their positions, as found by `from_chars`.
+pub type Markers = Vec<(char, Point<i64>)>;
+
+/// Parses one row per line, mapping each char through `f`; a `None` from `f`
+/// rejects the char. The positions of any `markers` are recorded in reading
+/// order, `x` being the line and `y` the char within it.
+pub fn from_chars<T: Clone>(
+    input: impl BufRead,
+    markers: &[char],
+    mut f: impl FnMut(char) -> Option<T>,
+) -> Result<(Matrix<T>, Markers)> {
+    let mut data = Vec::new();
+    let mut found = Vec::new();
+    let mut rows = 0;
+    let mut columns = None;
+    for line in numbered_lines(input) {
+        let (line_no, line_str) = line?;
+        let mut len = 0;
+        for (i, c) in line_str.char_indices() {
+            if markers.contains(&c) {
+                found.push((
+                    c,
+                    Point {
+                        x: rows as i64,
+                        y: len as i64,
+                    },
+                ));
+            }
+            let cell = f(c).ok_or_else(|| {
+                Error::unexpected_token(line_no, &line_str, &line_str[i..i + c.len_utf8()])
+            })?;
+            data.push(cell);
+            len += 1;
+        }
+        match columns {
+            Some(columns) if columns != len => {
+                return Err(Error::malformed(
+                    line_no,
+                    &line_str,
+                    &format!("expected {} columns like the lines before", columns),
+                ))
+            }
+            _ => columns = Some(len),
+        }
+        rows += 1;
+    }
+    Ok((from_nd(data, &[rows, columns.unwrap_or(0)]), found))
+}
+
+// widened so that large strides and indices cannot overflow on the way.
+fn get_offset<T>(matrix: &Matrix<T>, indices: &[usize]) -> usize {
+    let offset = matrix
         .strides
         .iter()
         .zip(indices)
-        .map(|(&stride, &index)| stride as i32 * index as i32)
-        .fold(matrix.start as i32, |sum, i| sum + i);
-    return (*matrix.data).borrow()[index_num as usize];
+        .map(|(&stride, &index)| stride as i64 * index as i64)
+        .fold(matrix.start as i64, |sum, i| sum + i);
+    usize::try_from(offset).expect("views never point before the data")
 }
 
-pub fn fliplr<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
-    assert!(matrix.strides.len() >= 2);
+fn check_bounds<T>(matrix: &Matrix<T>, indices: &[usize]) -> Result<()> {
+    let inside = indices.len() == matrix.shape.len()
+        && indices
+            .iter()
+            .zip(&matrix.shape)
+            .all(|(&index, &dim)| index < dim as usize);
+    if inside {
+        Ok(())
+    } else {
+        Err(Error::out_of_bounds(indices, &get_dims(&matrix.shape)))
+    }
+}
 
-    let mut strides = matrix.strides.to_vec();
-    let last_stride = strides.pop().expect("Last dimension");
-    strides.push(-1 * last_stride);
+pub fn try_get<'a, T>(matrix: &'a Matrix<T>, indices: &[usize]) -> Result<&'a T> {
+    check_bounds(matrix, indices)?;
+    Ok(&matrix.data[get_offset(matrix, indices)])
+}
+
+pub fn try_index<T: Clone + Copy>(matrix: &Matrix<T>, indices: &[usize]) -> Result<T> {
+    try_get(matrix, indices).copied()
+}
+
+/// Panics with the out-of-bounds error where `try_index` would return it.
+pub fn index<T: Clone + Copy>(matrix: &Matrix<T>, indices: &[usize]) -> T {
+    *get(matrix, indices)
+}
+
+// whether the elements are one row-major run of the buffer.
+fn is_contiguous<T>(matrix: &Matrix<T>) -> bool {
+    matrix.strides == get_contiguous_strides(&matrix.shape)
+}
+
+/// Copies the elements `matrix` sees into a new, unshared row-major buffer.
+pub fn to_owned<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
+    let len = matrix
+        .shape
+        .iter()
+        .map(|&dim| dim.max(0) as usize)
+        .product::<usize>();
+    let strides = get_contiguous_strides(&matrix.shape);
+    let data: Vec<T> = if is_contiguous(matrix) {
+        // already row-major, so the elements are one run of the buffer.
+        matrix.data[matrix.start..matrix.start + len].to_vec()
+    } else {
+        iter(matrix).cloned().collect()
+    };
     let back_strides = get_back_strides(&strides, &matrix.shape);
-    let last_dim = *matrix.shape.last().expect("Last dimension") as i32;
-    let mut start = matrix.start;
-    if last_stride < 0 {
-        start = (start as i32 - last_stride * (last_dim - 1)) as usize;
+    Matrix {
+        data: Arc::new(data),
+        start: 0,
+        shape: matrix.shape.to_vec(),
+        strides,
+        back_strides,
+    }
+}
+
+pub fn get<'a, T>(matrix: &'a Matrix<T>, indices: &[usize]) -> &'a T {
+    try_get(matrix, indices).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// A matrix sharing its data with other views is copied first, so those
+/// views never see changes made through the returned reference.
+pub fn get_mut<'a, T: Clone>(matrix: &'a mut Matrix<T>, indices: &[usize]) -> &'a mut T {
+    if let Err(err) = check_bounds(matrix, indices) {
+        panic!("{}", err);
+    }
+    if Arc::strong_count(&matrix.data) > 1 {
+        *matrix = to_owned(matrix);
+    }
+    let offset = get_offset(matrix, indices);
+    let data = Arc::get_mut(&mut matrix.data).expect("the data is no longer shared");
+    &mut data[offset]
+}
+
+/// Mutates one element through `f`, copying shared data first like `get_mut`.
+pub fn update<T: Clone>(matrix: &mut Matrix<T>, indices: &[usize], f: impl FnOnce(&mut T)) {
+    f(get_mut(matrix, indices));
+}
+
+pub fn set<T: Clone>(matrix: &mut Matrix<T>, indices: &[usize], value: T) {
+    update(matrix, indices, |element| *element = value);
+}
+
+// moves `indices` to the next one in row-major order, false after the last.
+fn step_index(indices: &mut [usize], shape: &[usize]) -> bool {
+    for axis in (0..shape.len()).rev() {
+        indices[axis] += 1;
+        if indices[axis] < shape[axis] {
+            return true;
+        }
+        indices[axis] = 0;
+    }
+    false
+}
+
+/// Every index of a shape in row-major order.
+pub struct Indices {
+    shape: Vec<usize>,
+    next: Option<Vec<usize>>,
+}
+
+impl Iterator for Indices {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let current = self.next.take()?;
+        let mut following = current.clone();
+        if step_index(&mut following, &self.shape) {
+            self.next = Some(following);
+        }
+        Some(current)
+    }
+}
+
+pub fn indices<T>(matrix: &Matrix<T>) -> Indices {
+    let shape: Vec<usize> = matrix
+        .shape
+        .iter()
+        .map(|&dim| dim.max(0) as usize)
+        .collect();
+    let next = if shape.contains(&0) {
+        None
     } else {
-        start = (start as i32 + last_stride * (last_dim - 1)) as usize;
+        Some(vec![0; shape.len()])
+    };
+    Indices { shape, next }
+}
+
+/// Each element with its index, in row-major order.
+pub fn indexed_iter<T>(matrix: &Matrix<T>) -> impl Iterator<Item = (Vec<usize>, &T)> {
+    indices(matrix).map(move |indices| {
+        let element = &matrix.data[get_offset(matrix, &indices)];
+        (indices, element)
+    })
+}
+
+/// The elements in row-major order.
+pub struct Iter<'a, T> {
+    matrix: &'a Matrix<T>,
+    indices: Vec<i32>,
+    offset: i64,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let element = &self.matrix.data[self.offset as usize];
+        self.remaining -= 1;
+        // step the odometer, moving the offset along with it.
+        for axis in (0..self.indices.len()).rev() {
+            self.indices[axis] += 1;
+            self.offset += self.matrix.strides[axis] as i64;
+            if self.indices[axis] < self.matrix.shape[axis] {
+                break;
+            }
+            self.indices[axis] = 0;
+            self.offset -= self.matrix.strides[axis] as i64 * self.matrix.shape[axis] as i64;
+        }
+        Some(element)
     }
-    return Matrix {
-        data: Rc::clone(&matrix.data),
-        start,
-        shape: matrix.shape.to_vec(),
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+pub fn iter<T>(matrix: &Matrix<T>) -> Iter<'_, T> {
+    Iter {
+        matrix,
+        indices: vec![0; matrix.shape.len()],
+        offset: matrix.start as i64,
+        remaining: matrix
+            .shape
+            .iter()
+            .map(|&dim| dim.max(0) as usize)
+            .product(),
+    }
+}
+
+pub fn rows<T: Clone>(matrix: &Matrix<T>) -> impl Iterator<Item = Matrix<T>> + '_ {
+    (0..matrix.shape[0] as usize).map(move |i| row(matrix, i))
+}
+
+pub fn columns<T: Clone>(matrix: &Matrix<T>) -> impl Iterator<Item = Matrix<T>> + '_ {
+    (0..matrix.shape[1] as usize).map(move |j| column(matrix, j))
+}
+
+/// Running `f` along `axis`: each element is combined with the result before
+/// it, scanning backwards from the end when `reverse` is set.
+pub fn scan_axis<T: Clone>(
+    matrix: &Matrix<T>,
+    axis: usize,
+    reverse: bool,
+    f: impl Fn(&T, &T) -> T,
+) -> Matrix<T> {
+    if reverse {
+        return flip_axis(&scan_axis(&flip_axis(matrix, axis), axis, false, f), axis);
+    }
+    let mut scanned = to_owned(matrix);
+    let strides: Vec<usize> = scanned.strides.iter().map(|&s| s as usize).collect();
+    let data = Arc::get_mut(&mut scanned.data).expect("a fresh copy is not shared");
+    // row-major order reaches the element before along `axis` first.
+    for indices in indices(matrix).filter(|indices| indices[axis] > 0) {
+        let offset: usize = indices.iter().zip(&strides).map(|(i, s)| i * s).sum();
+        data[offset] = f(&data[offset - strides[axis]], &data[offset]);
+    }
+    scanned
+}
+
+pub fn cummax<T: Clone + Ord>(matrix: &Matrix<T>, axis: usize, reverse: bool) -> Matrix<T> {
+    scan_axis(matrix, axis, reverse, |before, x| {
+        cmp::max(before, x).clone()
+    })
+}
+
+pub fn cummin<T: Clone + Ord>(matrix: &Matrix<T>, axis: usize, reverse: bool) -> Matrix<T> {
+    scan_axis(matrix, axis, reverse, |before, x| {
+        cmp::min(before, x).clone()
+    })
+}
+
+pub fn cumsum<T: Clone + std::ops::Add<Output = T>>(
+    matrix: &Matrix<T>,
+    axis: usize,
+    reverse: bool,
+) -> Matrix<T> {
+    scan_axis(matrix, axis, reverse, |before, x| {
+        before.clone() + x.clone()
+    })
+}
+
+pub fn map<T, U: Clone>(matrix: &Matrix<T>, f: impl Fn(&T) -> U) -> Matrix<U> {
+    let shape: Vec<usize> = get_dims(&matrix.shape);
+    from_nd(iter(matrix).map(f).collect(), &shape)
+}
+
+/// Combines the elements at the same index of two same-shaped matrices.
+pub fn zip_with<T, U, V: Clone>(
+    lhs: &Matrix<T>,
+    rhs: &Matrix<U>,
+    f: impl Fn(&T, &U) -> V,
+) -> Matrix<V> {
+    assert_eq!(lhs.shape, rhs.shape, "matrices must have the same shape");
+    let shape: Vec<usize> = get_dims(&lhs.shape);
+    let data = iter(lhs).zip(iter(rhs)).map(|(l, r)| f(l, r)).collect();
+    from_nd(data, &shape)
+}
+
+macro_rules! impl_elementwise_op {
+    ($trait:ident, $method:ident) => {
+        impl<T: Clone + ops::$trait<Output = T>> ops::$trait<&Matrix<T>> for &Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $method(self, rhs: &Matrix<T>) -> Matrix<T> {
+                zip_with(self, rhs, |l, r| l.clone().$method(r.clone()))
+            }
+        }
+
+        impl<T: Clone + ops::$trait<Output = T>> ops::$trait<&Matrix<T>> for Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $method(self, rhs: &Matrix<T>) -> Matrix<T> {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl<T: Clone + ops::$trait<Output = T>> ops::$trait<Matrix<T>> for Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $method(self, rhs: Matrix<T>) -> Matrix<T> {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl<T: Clone + ops::$trait<Output = T>> ops::$trait<T> for &Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $method(self, rhs: T) -> Matrix<T> {
+                map(self, |x| x.clone().$method(rhs.clone()))
+            }
+        }
+
+        impl<T: Clone + ops::$trait<Output = T>> ops::$trait<T> for Matrix<T> {
+            type Output = Matrix<T>;
+
+            fn $method(self, rhs: T) -> Matrix<T> {
+                (&self).$method(rhs)
+            }
+        }
+    };
+}
+
+impl_elementwise_op!(Add, add);
+impl_elementwise_op!(Sub, sub);
+impl_elementwise_op!(Mul, mul);
+impl_elementwise_op!(Div, div);
+
+pub fn sum<T: Clone + iter::Sum>(matrix: &Matrix<T>) -> T {
+    iter(matrix).cloned().sum()
+}
+
+/// The largest element, or `None` for an empty matrix.
+pub fn max<T: Clone + Ord>(matrix: &Matrix<T>) -> Option<T> {
+    iter(matrix).max().cloned()
+}
+
+/// The index of the first largest element, or `None` for an empty matrix.
+pub fn argmax<T: Ord>(matrix: &Matrix<T>) -> Option<Vec<usize>> {
+    let mut best: Option<(Vec<usize>, &T)> = None;
+    for (indices, element) in indexed_iter(matrix) {
+        if best.as_ref().is_none_or(|(_, max)| element > *max) {
+            best = Some((indices, element));
+        }
+    }
+    best.map(|(indices, _)| indices)
+}
+
+/// Applies `f` to every 1-d lane along `axis`; the result drops that axis.
+pub fn reduce_axis<T: Clone, U: Clone>(
+    matrix: &Matrix<T>,
+    axis: usize,
+    f: impl Fn(&Matrix<T>) -> U,
+) -> Matrix<U> {
+    assert!(axis < matrix.shape.len());
+    let mut shape: Vec<usize> = get_dims(&matrix.shape);
+    shape.remove(axis);
+    let reduced = from_nd(vec![(); shape.iter().product()], &shape);
+    let data = indices(&reduced)
+        .map(|mut indices| {
+            indices.insert(axis, 0);
+            let slices: Vec<Slice> = indices
+                .iter()
+                .enumerate()
+                .map(|(i, &index)| {
+                    if i == axis {
+                        ALL
+                    } else {
+                        Slice::Index(to_dim(index))
+                    }
+                })
+                .collect();
+            f(&slice(matrix, &slices))
+        })
+        .collect();
+    from_nd(data, &shape)
+}
+
+pub fn sum_axis<T: Clone + iter::Sum>(matrix: &Matrix<T>, axis: usize) -> Matrix<T> {
+    reduce_axis(matrix, axis, sum)
+}
+
+/// Panics on an empty axis, which has no largest element.
+pub fn max_axis<T: Clone + Ord>(matrix: &Matrix<T>, axis: usize) -> Matrix<T> {
+    reduce_axis(matrix, axis, |lane| {
+        max(lane).expect("an empty axis has no max")
+    })
+}
+
+pub fn argmax_axis<T: Clone + Ord>(matrix: &Matrix<T>, axis: usize) -> Matrix<usize> {
+    reduce_axis(matrix, axis, |lane| {
+        argmax(lane).expect("an empty axis has no argmax")[0]
+    })
+}
+
+/// One dimension of a `slice`, following numpy's `start:stop:step` rules.
+#[derive(Clone, Copy, Debug)]
+pub enum Slice {
+    /// A single position; the dimension is dropped. Negative counts from the end.
+    Index(i32),
+    /// `start:stop:step`, where `None` means the end the step moves away from.
+    Range(Option<i32>, Option<i32>, i32),
+}
+
+pub const ALL: Slice = Slice::Range(None, None, 1);
+
+// (first position, number of elements) of `start:stop:step` over `len` elements.
+fn resolve_range(start: Option<i32>, stop: Option<i32>, step: i32, len: i32) -> (i32, i32) {
+    assert!(step != 0, "slice step cannot be 0");
+    let wrap = |i: i32| if i < 0 { i + len } else { i };
+    // counted in i64 so that steps near the i32 limits cannot overflow.
+    let (first, count) = if step > 0 {
+        let first = start.map_or(0, |i| wrap(i).clamp(0, len));
+        let last = stop.map_or(len, |i| wrap(i).clamp(0, len));
+        let span = last as i64 - first as i64;
+        (first, (span + step as i64 - 1) / step as i64)
+    } else {
+        let first = start.map_or(len - 1, |i| wrap(i).clamp(-1, len - 1));
+        let last = stop.map_or(-1, |i| wrap(i).clamp(-1, len - 1));
+        let span = first as i64 - last as i64;
+        (first, (span - step as i64 - 1) / -(step as i64))
+    };
+    (first, cmp::max(0, count) as i32)
+}
+
+/// A zero-copy view of `matrix`. Dimensions without a `Slice` are kept whole.
+pub fn slice<T: Clone>(matrix: &Matrix<T>, slices: &[Slice]) -> Matrix<T> {
+    assert!(slices.len() <= matrix.shape.len());
+    let mut start = matrix.start as i64;
+    let mut shape = Vec::new();
+    let mut strides = Vec::new();
+    for (axis, (&dim, &stride)) in matrix.shape.iter().zip(&matrix.strides).enumerate() {
+        match slices.get(axis).copied().unwrap_or(ALL) {
+            Slice::Index(i) => {
+                let i = if i < 0 { i + dim } else { i };
+                assert!((0..dim).contains(&i), "index {} out of {} elements", i, dim);
+                start += stride as i64 * i as i64;
+            }
+            Slice::Range(first, last, step) => {
+                let (first, len) = resolve_range(first, last, step, dim);
+                if len > 0 {
+                    start += stride as i64 * first as i64;
+                }
+                shape.push(len);
+                // a single element is never stepped over, so any stride will do.
+                strides.push(if len <= 1 {
+                    stride
+                } else {
+                    stride.checked_mul(step).unwrap_or_else(|| {
+                        panic!("stride {} times step {} overflows i32", stride, step)
+                    })
+                });
+            }
+        }
+    }
+    let back_strides = get_back_strides(&strides, &shape);
+    Matrix {
+        data: Arc::clone(&matrix.data),
+        start: usize::try_from(start).expect("views never point before the data"),
+        shape,
         strides,
         back_strides,
+    }
+}
+
+pub fn row<T: Clone>(matrix: &Matrix<T>, i: usize) -> Matrix<T> {
+    slice(matrix, &[Slice::Index(to_dim(i))])
+}
+
+pub fn column<T: Clone>(matrix: &Matrix<T>, j: usize) -> Matrix<T> {
+    slice(matrix, &[ALL, Slice::Index(to_dim(j))])
+}
+
+/// The same elements in row-major order laid out over `shape`, sharing the
+/// data when `matrix` is already contiguous.
+pub fn reshape<T: Clone>(matrix: &Matrix<T>, shape: &[usize]) -> Matrix<T> {
+    let len: usize = matrix
+        .shape
+        .iter()
+        .map(|&dim| dim.max(0) as usize)
+        .product();
+    assert_eq!(
+        len,
+        shape.iter().product::<usize>(),
+        "cannot reshape {:?} into {:?}",
+        matrix.shape,
+        shape
+    );
+    let base = if is_contiguous(matrix) {
+        matrix.clone()
+    } else {
+        to_owned(matrix)
     };
+    let shape: Vec<i32> = shape.iter().map(|&dim| to_dim(dim)).collect();
+    let strides = get_contiguous_strides(&shape);
+    let back_strides = get_back_strides(&strides, &shape);
+    Matrix {
+        data: base.data,
+        start: base.start,
+        shape,
+        strides,
+        back_strides,
+    }
 }
 
-pub fn flipud<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
+/// A zero-copy view of the block `ranges` covers, one range per axis.
+pub fn crop<T: Clone>(matrix: &Matrix<T>, ranges: &[ops::Range<usize>]) -> Matrix<T> {
+    assert_eq!(ranges.len(), matrix.shape.len());
+    let slices: Vec<Slice> = ranges
+        .iter()
+        .zip(&matrix.shape)
+        .map(|(range, &dim)| {
+            assert!(
+                range.start <= range.end && range.end <= dim as usize,
+                "cannot crop {:?} out of {} elements",
+                range,
+                dim
+            );
+            Slice::Range(Some(to_dim(range.start)), Some(to_dim(range.end)), 1)
+        })
+        .collect();
+    slice(matrix, &slices)
+}
+
+/// A copy with `widths[axis] = (before, after)` extra elements of `fill`
+/// around each axis.
+pub fn pad<T: Clone>(matrix: &Matrix<T>, widths: &[(usize, usize)], fill: T) -> Matrix<T> {
+    assert_eq!(widths.len(), matrix.shape.len());
+    let shape: Vec<usize> = matrix
+        .shape
+        .iter()
+        .zip(widths)
+        .map(|(&dim, (before, after))| before + dim as usize + after)
+        .collect();
+    let strides = get_contiguous_strides(&shape.iter().map(|&dim| to_dim(dim)).collect::<Vec<_>>());
+    let mut data = vec![fill; shape.iter().product()];
+    let inner_shape: Vec<usize> = get_dims(&matrix.shape);
+    let mut indices = vec![0; inner_shape.len()];
+    for element in iter(matrix) {
+        let offset: usize = indices
+            .iter()
+            .zip(widths)
+            .zip(&strides)
+            .map(|((index, (before, _)), &stride)| (index + before) * stride as usize)
+            .sum();
+        data[offset] = element.clone();
+        step_index(&mut indices, &inner_shape);
+    }
+    from_nd(data, &shape)
+}
+
+/// Joins matrices end to end along `axis`; every other axis must match.
+pub fn concat<T: Clone>(matrices: &[&Matrix<T>], axis: usize) -> Matrix<T> {
+    assert!(!matrices.is_empty(), "nothing to concatenate");
+    let first = matrices[0];
+    assert!(axis < first.shape.len());
+    for matrix in matrices {
+        let mut expected = first.shape.clone();
+        expected[axis] = matrix.shape[axis];
+        assert_eq!(matrix.shape, expected, "shapes differ off axis {}", axis);
+    }
+    // with `axis` moved to the front, concatenating is appending row-major runs.
+    let mut axes: Vec<usize> = (0..first.shape.len()).collect();
+    axes.remove(axis);
+    axes.insert(0, axis);
+    let mut data = Vec::new();
+    for matrix in matrices {
+        data.extend(iter(&permute_axes(matrix, &axes)).cloned());
+    }
+    let mut shape: Vec<usize> = axes.iter().map(|&a| first.shape[a] as usize).collect();
+    shape[0] = matrices.iter().map(|m| m.shape[axis] as usize).sum();
+    let mut inverse = vec![0; axes.len()];
+    for (i, &a) in axes.iter().enumerate() {
+        inverse[a] = i;
+    }
+    to_owned(&permute_axes(&from_nd(data, &shape), &inverse))
+}
+
+/// Repeats `matrix` `reps[axis]` times along each axis.
+pub fn tile<T: Clone>(matrix: &Matrix<T>, reps: &[usize]) -> Matrix<T> {
+    assert_eq!(reps.len(), matrix.shape.len());
+    reps.iter()
+        .enumerate()
+        .fold(matrix.clone(), |tiled, (axis, &rep)| {
+            if rep == 0 {
+                // nothing repeated leaves the axis empty.
+                let mut shape: Vec<usize> = tiled.shape.iter().map(|&d| d as usize).collect();
+                shape[axis] = 0;
+                return from_nd(Vec::new(), &shape);
+            }
+            concat(&vec![&tiled; rep], axis)
+        })
+}
+
+/// Reverses the order of elements along `axis`.
+pub fn flip_axis<T: Clone>(matrix: &Matrix<T>, axis: usize) -> Matrix<T> {
+    assert!(axis < matrix.shape.len());
+
     let mut strides = matrix.strides.to_vec();
-    let first_stride = strides[0];
-    strides[0] = -strides[0];
+    strides[axis] = -strides[axis];
     let back_strides = get_back_strides(&strides, &matrix.shape);
-    let first_dim = *matrix.shape.first().expect("first dimension") as i32;
-    let mut start = matrix.start;
-    if first_stride < 0 {
-        start = (start as i32 - first_stride * (first_dim - 1)) as usize;
-    } else {
-        start = (start as i32 + first_stride * (first_dim - 1)) as usize;
-    }
-    return Matrix {
-        data: Rc::clone(&matrix.data),
-        start,
+    // the old last element along the axis becomes the first one.
+    let start =
+        matrix.start as i64 + matrix.strides[axis] as i64 * (matrix.shape[axis] - 1).max(0) as i64;
+    Matrix {
+        data: Arc::clone(&matrix.data),
+        start: usize::try_from(start).expect("views never point before the data"),
         shape: matrix.shape.to_vec(),
         strides,
         back_strides,
-    };
+    }
+}
+
+/// Reverses the columns, i.e. axis 1, as numpy's `fliplr` does.
+pub fn fliplr<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
+    assert!(matrix.strides.len() >= 2);
+    flip_axis(matrix, 1)
 }
 
+pub fn flipud<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
+    flip_axis(matrix, 0)
+}
+
+/// Reverses every axis.
 #[allow(dead_code, unused_imports)]
 pub fn flip<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
-    return fliplr(&flipud(&matrix));
+    (0..matrix.shape.len()).fold(matrix.clone(), |m, axis| flip_axis(&m, axis))
 }
 
-pub fn transpose<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
-    let data = Rc::clone(&(*matrix).data);
-    let strides = matrix.strides.iter().rev().cloned().collect();
-    let shape = matrix.shape.iter().rev().cloned().collect();
+/// A view whose axis `i` is axis `axes[i]` of `matrix`.
+pub fn permute_axes<T: Clone>(matrix: &Matrix<T>, axes: &[usize]) -> Matrix<T> {
+    let mut sorted = axes.to_vec();
+    sorted.sort_unstable();
+    assert!(
+        sorted.iter().copied().eq(0..matrix.shape.len()),
+        "{:?} is not a permutation of {} axes",
+        axes,
+        matrix.shape.len()
+    );
+    let shape: Vec<i32> = axes.iter().map(|&axis| matrix.shape[axis]).collect();
+    let strides: Vec<i32> = axes.iter().map(|&axis| matrix.strides[axis]).collect();
     let back_strides = get_back_strides(&strides, &shape);
-    return Matrix {
-        data,
+    Matrix {
+        data: Arc::clone(&matrix.data),
         start: matrix.start,
         shape,
         strides,
         back_strides,
-    };
+    }
 }
 
+/// Reverses the order of the axes.
+pub fn transpose<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
+    let axes: Vec<usize> = (0..matrix.shape.len()).rev().collect();
+    permute_axes(matrix, &axes)
+}
+
+/// Rotates the first two axes by 90 degrees `k` times, counter-clockwise for
+/// positive `k` and clockwise for negative `k`.
 pub fn rot90<T: Clone>(matrix: &Matrix<T>, k: i64) -> Matrix<T> {
-    let _k = k % 4;
-    return match _k {
+    assert!(matrix.shape.len() >= 2);
+    // swaps axes 0 and 1, leaving any trailing axes in place.
+    let mut axes: Vec<usize> = (0..matrix.shape.len()).collect();
+    axes.swap(0, 1);
+
+    match k.rem_euclid(4) {
         0 => matrix.clone(),
-        1 => transpose(&fliplr(&matrix)),
-        2 => fliplr(&flipud(&matrix)),
-        _ => fliplr(&transpose(&matrix)),
-    };
+        1 => permute_axes(&flip_axis(matrix, 1), &axes),
+        2 => flip_axis(&flip_axis(matrix, 0), 1),
+        _ => flip_axis(&permute_axes(matrix, &axes), 1),
+    }
 }
 
 #[cfg(test)]
@@ -223,9 +1117,9 @@ mod tests {
 
     #[test]
     fn test_from_2d() {
-        let t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
-        assert_eq!(index(&t, &[0 as usize, 0 as usize]), 1);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 4);
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(index(&t, &[0, 0]), 1);
+        assert_eq!(index(&t, &[1, 0]), 4);
         assert_eq!(t.shape[0], 2);
         assert_eq!(t.shape[1], 3);
         assert_eq!(t.strides[0], 3);
@@ -234,81 +1128,514 @@ mod tests {
 
     #[test]
     fn test_from_1d() {
-        let t = from_1d::<i32>(Rc::new(RefCell::new(vec![1, 2, 3, 4, 5, 6])));
-        assert_eq!(index(&t, &[0 as usize]), 1);
-        assert_eq!(index(&t, &[3 as usize]), 4);
+        let t = from_1d::<i32>(vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(index(&t, &[0]), 1);
+        assert_eq!(index(&t, &[3]), 4);
         assert_eq!(t.shape[0], 6);
         assert_eq!(t.strides[0], 1);
     }
 
     #[test]
     fn test_index_access() {
-        let t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
-        assert_eq!(index(&t, &[0 as usize, 2 as usize]), 3);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 4);
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(index(&t, &[0, 2]), 3);
+        assert_eq!(index(&t, &[1, 0]), 4);
     }
 
     #[test]
     fn test_transpose() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = transpose(&t);
-        assert_eq!(index(&t, &[0 as usize, 1 as usize]), 4);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 2);
+        assert_eq!(index(&t, &[0, 1]), 4);
+        assert_eq!(index(&t, &[1, 0]), 2);
     }
 
     #[test]
     fn test_fliplr() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = fliplr(&t);
-        assert_eq!(index(&t, &[0 as usize, 2 as usize]), 1);
-        assert_eq!(index(&t, &[1 as usize, 2 as usize]), 4);
+        assert_eq!(index(&t, &[0, 2]), 1);
+        assert_eq!(index(&t, &[1, 2]), 4);
     }
 
     #[test]
     fn test_flipud() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = flipud(&t);
-        assert_eq!(index(&t, &[0 as usize, 1 as usize]), 5);
-        assert_eq!(index(&t, &[1 as usize, 2 as usize]), 3);
+        assert_eq!(index(&t, &[0, 1]), 5);
+        assert_eq!(index(&t, &[1, 2]), 3);
     }
 
     #[test]
     fn test_flip() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = flip(&t);
-        assert_eq!(index(&t, &[0 as usize, 2 as usize]), 4);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 3);
+        assert_eq!(index(&t, &[0, 2]), 4);
+        assert_eq!(index(&t, &[1, 0]), 3);
     }
 
     #[test]
     fn test_rot90_0_time() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = rot90(&t, 0);
-        assert_eq!(index(&t, &[0 as usize, 1 as usize]), 2);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 4);
+        assert_eq!(index(&t, &[0, 1]), 2);
+        assert_eq!(index(&t, &[1, 0]), 4);
     }
 
     #[test]
     fn test_rot90_1_time() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = rot90(&t, 1);
-        assert_eq!(index(&t, &[0 as usize, 1 as usize]), 6);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 2);
+        assert_eq!(index(&t, &[0, 1]), 6);
+        assert_eq!(index(&t, &[1, 0]), 2);
     }
 
     #[test]
     fn test_rot90_2_times() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = rot90(&t, 2);
-        assert_eq!(index(&t, &[0 as usize, 1 as usize]), 5);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 3);
+        assert_eq!(index(&t, &[0, 1]), 5);
+        assert_eq!(index(&t, &[1, 0]), 3);
     }
 
     #[test]
     fn test_rot90_3_times() {
-        let mut t = from_2d::<i32>(&vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
         t = rot90(&t, 3);
-        assert_eq!(index(&t, &[0 as usize, 1 as usize]), 1);
-        assert_eq!(index(&t, &[1 as usize, 0 as usize]), 5);
+        assert_eq!(index(&t, &[0, 1]), 1);
+        assert_eq!(index(&t, &[1, 0]), 5);
+    }
+
+    #[test]
+    fn test_flip_twice() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let lr = fliplr(&fliplr(&t));
+        let ud = flipud(&flipud(&t));
+        assert_eq!(index(&lr, &[0, 0]), 1);
+        assert_eq!(index(&lr, &[1, 2]), 6);
+        assert_eq!(index(&ud, &[0, 0]), 1);
+        assert_eq!(index(&ud, &[1, 2]), 6);
+    }
+
+    #[test]
+    fn test_to_owned() {
+        let t = rot90(&from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]), 1);
+        let owned = to_owned(&t);
+        assert_eq!(owned.shape, vec![3, 2]);
+        assert_eq!(owned.strides, vec![2, 1]);
+        assert_eq!(*owned.data, vec![3, 6, 2, 5, 1, 4]);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        set(&mut t, &[1, 2], 9);
+        assert_eq!(index(&t, &[1, 2]), 9);
+        update(&mut t, &[0, 0], |x| *x += 10);
+        assert_eq!(index(&t, &[0, 0]), 11);
+    }
+
+    #[test]
+    fn test_set_on_view_copies() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let mut rotated = rot90(&t, 1);
+        set(&mut rotated, &[0, 0], 7);
+        assert_eq!(index(&rotated, &[0, 0]), 7);
+        assert_eq!(index(&rotated, &[2, 1]), 4);
+        assert_eq!(index(&t, &[0, 2]), 3);
+
+        let mut flipped = flipud(&fliplr(&t));
+        set(&mut flipped, &[0, 0], 8);
+        assert_eq!(index(&flipped, &[0, 0]), 8);
+        assert_eq!(index(&flipped, &[1, 2]), 1);
+        assert_eq!(index(&t, &[1, 2]), 6);
+    }
+
+    #[test]
+    fn test_views_across_threads() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let rotated = std::thread::scope(|scope| {
+            let handle = scope.spawn(|| rot90(&t, 1));
+            handle.join().unwrap()
+        });
+        assert_eq!(index(&rotated, &[0, 1]), 6);
+    }
+
+    #[test]
+    fn test_slice_ranges() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3, 4], vec![5, 6, 7, 8], vec![9, 10, 11, 12]]);
+        let sub = slice(
+            &t,
+            &[
+                Slice::Range(Some(1), None, 1),
+                Slice::Range(Some(1), Some(-1), 1),
+            ],
+        );
+        assert_eq!(sub.shape, vec![2, 2]);
+        assert_eq!(index(&sub, &[0, 0]), 6);
+        assert_eq!(index(&sub, &[1, 1]), 11);
+
+        let stepped = slice(&t, &[ALL, Slice::Range(None, None, 2)]);
+        assert_eq!(stepped.shape, vec![3, 2]);
+        assert_eq!(index(&stepped, &[2, 1]), 11);
+
+        let reversed = slice(
+            &t,
+            &[
+                Slice::Range(None, None, -1),
+                Slice::Range(Some(-1), Some(0), -2),
+            ],
+        );
+        assert_eq!(reversed.shape, vec![3, 2]);
+        assert_eq!(index(&reversed, &[0, 0]), 12);
+        assert_eq!(index(&reversed, &[2, 1]), 2);
+
+        let empty = slice(&t, &[Slice::Range(Some(2), Some(1), 1)]);
+        assert_eq!(empty.shape, vec![0, 4]);
+        let single = slice(&t, &[Slice::Range(None, None, i32::MAX), ALL]);
+        assert_eq!(single.shape, vec![1, 4]);
+        assert_eq!(index(&single, &[0, 3]), 4);
+    }
+
+    #[test]
+    fn test_slice_huge_step() {
+        let t = from_2d::<i32>(&[vec![1, 2], vec![3, 4]]);
+        let first = slice(
+            &t,
+            &[
+                Slice::Range(None, None, i32::MAX),
+                Slice::Range(None, None, i32::MAX),
+            ],
+        );
+        assert_eq!(first.shape, vec![1, 1]);
+        assert_eq!(index(&first, &[0, 0]), 1);
+        let last = slice(
+            &t,
+            &[
+                Slice::Range(None, None, i32::MIN),
+                Slice::Range(None, None, i32::MIN),
+            ],
+        );
+        assert_eq!(last.shape, vec![1, 1]);
+        assert_eq!(index(&last, &[0, 0]), 4);
+    }
+
+    #[test]
+    fn test_slice_drops_dimension() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let r = row(&t, 1);
+        assert_eq!(r.shape, vec![3]);
+        assert_eq!(index(&r, &[2]), 6);
+        let c = column(&rot90(&t, 1), 0);
+        assert_eq!(c.shape, vec![3]);
+        assert_eq!(index(&c, &[0]), 3);
+        assert_eq!(index(&c, &[2]), 1);
+        let last = slice(&t, &[Slice::Index(-1), Slice::Index(-1)]);
+        assert_eq!(index(&last, &[]), 6);
+    }
+
+    #[test]
+    fn test_from_nd() {
+        let t = from_nd((0..24).collect(), &[2, 3, 4]);
+        assert_eq!(t.strides, vec![12, 4, 1]);
+        assert_eq!(index(&t, &[1, 2, 3]), 23);
+        assert_eq!(index(&t, &[1, 0, 2]), 14);
+        let z = zeros::<u8>(&[2, 2, 2]);
+        assert_eq!(index(&z, &[1, 1, 1]), 0);
+    }
+
+    #[test]
+    fn test_flip_and_permute_axes() {
+        let t = from_nd((0..24).collect(), &[2, 3, 4]);
+        let flipped = flip_axis(&t, 1);
+        assert_eq!(index(&flipped, &[0, 0, 0]), 8);
+        assert_eq!(index(&flipped, &[1, 2, 3]), 15);
+        assert_eq!(index(&flip(&t), &[0, 0, 0]), 23);
+
+        let permuted = permute_axes(&t, &[2, 0, 1]);
+        assert_eq!(permuted.shape, vec![4, 2, 3]);
+        assert_eq!(index(&permuted, &[3, 1, 2]), 23);
+        assert_eq!(index(&permuted, &[1, 0, 2]), 9);
+        assert_eq!(transpose(&t).shape, vec![4, 3, 2]);
+        assert_eq!(index(&transpose(&t), &[3, 1, 0]), 7);
+    }
+
+    #[test]
+    fn test_display() {
+        let t = from_nd((0..8).collect::<Vec<i32>>(), &[2, 2, 2]);
+        assert_eq!(t.to_string(), "[[[0,1],\n  [2,3]],\n [[4,5],\n  [6,7]]]");
+        assert_eq!(from_1d(vec![1, 2, 3]).to_string(), "[1,2,3]");
+    }
+
+    #[test]
+    fn test_iterators() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let rotated = rot90(&t, 1);
+        assert_eq!(
+            iter(&rotated).copied().collect::<Vec<_>>(),
+            vec![3, 6, 2, 5, 1, 4]
+        );
+        let (last_index, last) = indexed_iter(&t).last().unwrap();
+        assert_eq!((last_index, *last), (vec![1, 2], 6));
+        let row_sums: Vec<i32> = rows(&t).map(|r| iter(&r).sum()).collect();
+        assert_eq!(row_sums, vec![6, 15]);
+        let column_sums: Vec<i32> = columns(&t).map(|c| iter(&c).sum()).collect();
+        assert_eq!(column_sums, vec![5, 7, 9]);
+    }
+
+    #[test]
+    fn test_scans() {
+        let t = from_2d::<i32>(&[vec![3, 1, 4], vec![1, 5, 9], vec![2, 6, 5]]);
+        let scanned: Vec<i32> = iter(&cummax(&t, 1, false)).copied().collect();
+        assert_eq!(scanned, vec![3, 3, 4, 1, 5, 9, 2, 6, 6]);
+        let scanned: Vec<i32> = iter(&cummax(&t, 0, true)).copied().collect();
+        assert_eq!(scanned, vec![3, 6, 9, 2, 6, 9, 2, 6, 5]);
+        let scanned: Vec<i32> = iter(&cummin(&t, 1, true)).copied().collect();
+        assert_eq!(scanned, vec![1, 1, 4, 1, 5, 9, 2, 5, 5]);
+        let scanned: Vec<i32> = iter(&cumsum(&transpose(&t), 1, false)).copied().collect();
+        assert_eq!(scanned, vec![3, 4, 6, 1, 6, 12, 4, 13, 18]);
+    }
+
+    #[test]
+    fn test_map_and_ops() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let doubled = map(&t, |x| x * 2);
+        assert_eq!(index(&doubled, &[1, 2]), 12);
+        let flipped = flip(&t);
+        let sums = &t + &flipped;
+        assert!(iter(&sums).all(|&x| x == 7));
+        let product = (&t * &t) - 1;
+        assert_eq!(index(&product, &[1, 1]), 24);
+        let halves = zip_with(&t, &doubled, |x, y| *y as f64 / (*x as f64 * 4.0));
+        assert_eq!(index(&halves, &[0, 1]), 0.5);
+    }
+
+    #[test]
+    fn test_reductions() {
+        let t = from_2d::<i32>(&[vec![3, 9, 4], vec![1, 5, 9]]);
+        assert_eq!(sum(&t), 31);
+        assert_eq!(max(&t), Some(9));
+        assert_eq!(argmax(&t), Some(vec![0, 1]));
+        assert_eq!(
+            iter(&sum_axis(&t, 0)).copied().collect::<Vec<_>>(),
+            vec![4, 14, 13]
+        );
+        assert_eq!(
+            iter(&max_axis(&t, 1)).copied().collect::<Vec<_>>(),
+            vec![9, 9]
+        );
+        assert_eq!(
+            iter(&argmax_axis(&t, 1)).copied().collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(max(&from_1d(Vec::<i32>::new())), None);
+    }
+
+    #[test]
+    fn test_from_chars() {
+        let (t, markers) = from_chars("a#S\n.E#\n".as_bytes(), &['S', 'E'], |c| match c {
+            '#' => Some(1),
+            '.' | 'a' | 'S' | 'E' => Some(0),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(t.shape, vec![2, 3]);
+        assert_eq!(
+            iter(&t).copied().collect::<Vec<_>>(),
+            vec![0, 1, 0, 0, 0, 1]
+        );
+        assert_eq!(
+            markers,
+            vec![('S', Point { x: 0, y: 2 }), ('E', Point { x: 1, y: 1 })]
+        );
+
+        let ragged = from_chars("..\n...\n".as_bytes(), &[], |_| Some(0)).unwrap_err();
+        assert!(matches!(ragged, Error::MalformedLine { line: 2, .. }));
+        let unknown = from_chars("..\n.x\n".as_bytes(), &[], |c| (c == '.').then_some(0));
+        assert!(matches!(
+            unknown.unwrap_err(),
+            Error::UnexpectedToken {
+                line: 2,
+                column: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_reshape() {
+        let t = from_nd((0..6).collect::<Vec<i32>>(), &[2, 3]);
+        let r = reshape(&t, &[3, 2]);
+        assert!(Arc::ptr_eq(&r.data, &t.data));
+        assert_eq!(index(&r, &[2, 0]), 4);
+        let flipped = reshape(&fliplr(&t), &[6]);
+        assert_eq!(
+            iter(&flipped).copied().collect::<Vec<_>>(),
+            vec![2, 1, 0, 5, 4, 3]
+        );
+    }
+
+    #[test]
+    fn test_crop_and_pad() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let cropped = crop(&flipud(&t), &[0..1, 1..3]);
+        assert_eq!(iter(&cropped).copied().collect::<Vec<_>>(), vec![5, 6]);
+        let padded = pad(&fliplr(&t), &[(1, 0), (0, 2)], 0);
+        assert_eq!(padded.shape, vec![3, 5]);
+        assert_eq!(
+            iter(&padded).copied().collect::<Vec<_>>(),
+            vec![0, 0, 0, 0, 0, 3, 2, 1, 0, 0, 6, 5, 4, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_concat_and_tile() {
+        let t = from_2d::<i32>(&[vec![1, 2], vec![3, 4]]);
+        let rows = concat(&[&t, &flipud(&t)], 0);
+        assert_eq!(rows.shape, vec![4, 2]);
+        assert_eq!(
+            iter(&rows).copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 3, 4, 1, 2]
+        );
+        let columns = concat(&[&t, &from_2d(&[vec![9], vec![8]])], 1);
+        assert_eq!(
+            iter(&columns).copied().collect::<Vec<_>>(),
+            vec![1, 2, 9, 3, 4, 8]
+        );
+        let tiled = tile(&transpose(&t), &[2, 2]);
+        assert_eq!(tiled.shape, vec![4, 4]);
+        assert_eq!(
+            iter(&row(&tiled, 3)).copied().collect::<Vec<_>>(),
+            vec![2, 4, 2, 4]
+        );
+    }
+
+    #[test]
+    fn test_try_index() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(try_index(&t, &[1, 2]).unwrap(), 6);
+        let err = try_index(&t, &[2, 0]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "index [2, 0] is out of bounds for shape [2, 3]"
+        );
+        assert!(try_index(&t, &[0]).is_err());
+        assert!(try_index(&rot90(&t, 1), &[2, 1]).is_ok());
+        assert!(try_get(&row(&t, 0), &[3]).is_err());
+    }
+
+    #[test]
+    fn test_rot90_any_k() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let clockwise = rot90(&t, -1);
+        assert_eq!(clockwise.shape, vec![3, 2]);
+        assert_eq!(index(&clockwise, &[0, 0]), 4);
+        assert_eq!(index(&clockwise, &[0, 1]), 1);
+        for k in -9_i64..9 {
+            // a quarter turn clockwise is three counter-clockwise ones.
+            let turns = if k < 0 { -3 * k } else { k };
+            let turned = (0..turns).fold(t.clone(), |m, _| rot90(&m, 1));
+            let expected: Vec<i32> = iter(&turned).copied().collect();
+            assert_eq!(iter(&rot90(&t, k)).copied().collect::<Vec<_>>(), expected);
+        }
+        assert_eq!(
+            iter(&rot90(&rot90(&t, 1), -1)).copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn test_point_ops() {
+        let a = Point { x: 1_i64, y: -2 };
+        let b = Point { x: 3, y: 4 };
+        assert_eq!(&a + &b, Point { x: 4, y: 2 });
+        assert_eq!(a.clone() - b.clone(), Point { x: -2, y: -6 });
+        assert_eq!(b.clone() * 2, Point { x: 6, y: 8 });
+        assert_eq!(-a.clone(), Point { x: -1, y: 2 });
+        let mut c = a.clone();
+        c += b.clone();
+        c -= a.clone();
+        assert_eq!(c, b);
+        let p = Point3 { x: 1, y: 2, z: 3 } + Point3 { x: 1, y: 1, z: 1 };
+        assert_eq!(p, Point3 { x: 2, y: 3, z: 4 });
+    }
+
+    #[test]
+    fn test_distances() {
+        let a = Point { x: 1_i64, y: -2 };
+        let b = Point { x: 4, y: 2 };
+        assert_eq!(manhattan_distance(&a, &b), 7);
+        assert_eq!(chebyshev_distance(&a, &b), 4);
+        assert_eq!(euclidean_distance_squared(&a, &b), 25);
+        let p = Point3 {
+            x: 5_u32,
+            y: 1,
+            z: 2,
+        };
+        let q = Point3 {
+            x: 2_u32,
+            y: 3,
+            z: 2,
+        };
+        assert_eq!(manhattan_distance(&p, &q), 5);
+        assert_eq!(chebyshev_distance(&p, &q), 3);
+        assert_eq!(euclidean_distance_squared(&p, &q), 13);
+        assert_eq!(checked_manhattan_distance(&a, &b), Some(7));
+        let far = Point { x: i64::MAX, y: 0 };
+        let near = Point { x: -i64::MAX, y: 0 };
+        assert_eq!(checked_manhattan_distance(&far, &near), None);
+        let edge = Point { x: i64::MAX, y: 0 };
+        let corner = Point { x: 0, y: -i64::MAX };
+        assert_eq!(checked_manhattan_distance(&edge, &corner), None);
+    }
+
+    #[test]
+    fn test_direction() {
+        assert_eq!(Direction::parse("U"), Some(Direction::Up));
+        assert_eq!(Direction::parse(">"), Some(Direction::Right));
+        assert_eq!(Direction::parse("x"), None);
+        for dir in Direction::ALL {
+            assert_eq!(dir.turn_left().turn_right(), dir);
+            assert_eq!(dir.turn_right().turn_right(), dir.opposite());
+            assert_eq!(-dir.delta(), dir.opposite().delta());
+        }
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Up.delta(), Point { x: -1, y: 0 });
+    }
+
+    #[test]
+    fn test_rot90_3d() {
+        // two rows, three columns, each cell holding a pair.
+        let t = from_nd::<i32>((0..12).collect(), &[2, 3, 2]);
+        let turned = rot90(&t, 1);
+        assert_eq!(turned.shape, vec![3, 2, 2]);
+        for i in 0..2 {
+            for j in 0..3 {
+                for c in 0..2 {
+                    let value = index(&t, &[i, j, c]);
+                    assert_eq!(index(&turned, &[2 - j, i, c]), value);
+                    assert_eq!(index(&rot90(&t, 2), &[1 - i, 2 - j, c]), value);
+                    assert_eq!(index(&rot90(&t, -1), &[j, 1 - i, c]), value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tile_zero_reps() {
+        let t = from_2d::<i32>(&[vec![1, 2, 3], vec![4, 5, 6]]);
+        let empty = tile(&t, &[0, 1]);
+        assert_eq!(empty.shape, vec![0, 3]);
+        assert_eq!(iter(&empty).count(), 0);
+        assert_eq!(tile(&t, &[2, 0]).shape, vec![4, 0]);
+    }
+
+    #[test]
+    fn test_fliplr_3d() {
+        let t = from_nd::<i32>((0..12).collect(), &[2, 3, 2]);
+        let flipped = fliplr(&t);
+        assert_eq!(index(&flipped, &[0, 0, 0]), 4);
+        assert_eq!(index(&flipped, &[0, 0, 1]), 5);
+        assert_eq!(index(&flipped, &[1, 2, 1]), 7);
     }
 }
@@ -0,0 +1,65 @@
+use crate::error::Error;
+use crate::error::Result;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Puzzle parameters given as `key=value` on the command line. A day reads
+/// each one with its own default, so an empty set solves the real puzzle.
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    /// Adds a `key=value` assignment, returning `None` when there is no `=`.
+    pub fn insert_assignment(&mut self, assignment: &str) -> Option<()> {
+        let (key, value) = assignment.split_once('=')?;
+        self.insert(key.trim(), value.trim());
+        Some(())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(|k| k.as_str())
+    }
+
+    /// The parameters whose key is one of `keys`.
+    pub fn only(&self, keys: &[&str]) -> Params {
+        Params {
+            values: self
+                .values
+                .iter()
+                .filter(|(k, _)| keys.contains(&k.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str, default: T) -> Result<T> {
+        match self.values.get(key) {
+            None => Ok(default),
+            Some(value) => value.parse::<T>().map_err(|_| {
+                Error::invalid_state(None, &format!("parameter {} cannot be {:?}", key, value))
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let mut params = Params::default();
+        params.insert_assignment("target_y=10").unwrap();
+        assert!(params.insert_assignment("search_bound").is_none());
+        assert_eq!(params.get("target_y", 2000000_i64).unwrap(), 10);
+        assert_eq!(params.get("search_bound", 4000000_i64).unwrap(), 4000000);
+        params.insert("num_rocks", "many");
+        assert!(params.get("num_rocks", 2022_usize).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use crate::bench;
+use crate::bench::Bench;
+use crate::error::Result;
+use crate::params::Params;
+use crate::report;
+use crate::report::Record;
+use crate::solution;
+use crate::solution::Answer;
+use crate::solution::Solution;
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+pub struct Puzzle {
+    pub day: u32,
+    pub part: u32,
+    pub name: &'static str,
+    pub params: &'static [&'static str],
+    pub solve: fn(&mut dyn BufRead, &Params) -> Result<Answer>,
+    pub bench: fn(&str, usize, &Params) -> Result<Bench>,
+}
+
+macro_rules! puzzle {
+    ($day:literal, 1, $module:ident :: $solver:ident, $func:ident) => {
+        puzzle!(
+            @entry $day, 1, $module, $func,
+            <crate::$module::$solver as Solution>::PARAMS,
+            solution::solve_part1::<crate::$module::$solver>,
+            bench::bench_part1::<crate::$module::$solver>
+        )
+    };
+    ($day:literal, 2, $module:ident :: $solver:ident, $func:ident) => {
+        puzzle!(
+            @entry $day, 2, $module, $func,
+            <crate::$module::$solver as Solution>::PARAMS,
+            solution::solve_part2::<crate::$module::$solver>,
+            bench::bench_part2::<crate::$module::$solver>
+        )
+    };
+    (
+        @entry $day:literal, $part:literal, $module:ident, $func:ident,
+        $params:expr, $solve:expr, $bench:expr
+    ) => {
+        Puzzle {
+            day: $day,
+            part: $part,
+            name: concat!(stringify!($module), "::", stringify!($func)),
+            params: $params,
+            solve: $solve,
+            bench: $bench,
+        }
+    };
+}
+
+pub const PUZZLES: [Puzzle; 34] = [
+    puzzle!(1, 1, day1::Day1, max_calories_elf),
+    puzzle!(1, 2, day1::Day1, total_top_3_calories_elf),
+    puzzle!(2, 1, day2::Day2, score_by_guide),
+    puzzle!(2, 2, day2::Day2, score_by_secret_guide),
+    puzzle!(3, 1, day3::Day3, get_priorities),
+    puzzle!(3, 2, day3::Day3, get_priorities_owned_by_3_elf),
+    puzzle!(4, 1, day4::Day4, count_segments_inclusions),
+    puzzle!(4, 2, day4::Day4, count_segments_overlap),
+    puzzle!(5, 1, day5::Day5, top_crate_after_moving),
+    puzzle!(5, 2, day5::Day5, top_crate_after_moving_with_new_crane),
+    puzzle!(6, 1, day6::Day6, first_marker),
+    puzzle!(6, 2, day6::Day6, first_marker_for_message),
+    puzzle!(7, 1, day7::Day7, sum_bound_dirs),
+    puzzle!(7, 2, day7::Day7, smallest_dirs_remove),
+    puzzle!(8, 1, day8::Day8, sum_visible_trees),
+    puzzle!(8, 2, day8::Day8, max_visible_trees),
+    puzzle!(9, 1, day9::Day9, sum_tail_visited),
+    puzzle!(9, 2, day9::Day9, sum_last_tail_visited),
+    puzzle!(10, 1, day10::Day10, sum_signal_strength),
+    puzzle!(10, 2, day10::Day10, render_images),
+    puzzle!(11, 1, day11::Day11, get_two_most_active_monkey),
+    puzzle!(11, 2, day11::Day11, get_two_most_active_monkey_many_rounds),
+    puzzle!(12, 1, day12::Day12, min_steps_in_hill),
+    puzzle!(12, 2, day12::Day12, min_steps_from_a_in_hill),
+    puzzle!(13, 1, day13::Day13, get_distress_signal),
+    puzzle!(13, 2, day13::Day13, get_distress_signal_decorder_key),
+    puzzle!(14, 1, day14::Day14, simulate_filled_sand),
+    puzzle!(14, 2, day14::Day14, simulate_stable_sand),
+    puzzle!(15, 1, day15::Day15, get_num_positions_no_beacon),
+    puzzle!(15, 2, day15::Day15, get_distress_beacon),
+    puzzle!(16, 1, day16::Day16, get_max_flow),
+    puzzle!(16, 2, day16::Day16, get_max_flow_with_elephant),
+    puzzle!(17, 1, day17::Day17, simulate_tetris),
+    puzzle!(17, 2, day17::Day17, simulate_long_tetris),
+];
+
+pub fn find(day: u32, part: u32) -> Option<&'static Puzzle> {
+    PUZZLES.iter().find(|p| p.day == day && p.part == part)
+}
+
+impl Puzzle {
+    /// Solves `input`, timing the parse and the part together.
+    pub fn solve_timed(&self, input: &str, params: &Params) -> Result<Record> {
+        let start = Instant::now();
+        let answer = (self.solve)(&mut input.as_bytes(), params)?;
+        Ok(Record {
+            day: self.day,
+            part: self.part,
+            answer,
+            elapsed: start.elapsed(),
+            input_digest: report::digest(input.as_bytes()),
+        })
+    }
+}
+
+pub enum Outcome {
+    Solved(Record),
+    Missing(String),
+    Failed(String),
+}
+
+/// Where `run --all` looks for a day's input inside `dir`.
+pub fn input_path(dir: &Path, day: u32) -> PathBuf {
+    dir.join(format!("day{}.txt", day))
+}
+
+/// Solves every puzzle on its input in `dir`, spreading them across threads.
+/// Each day only sees the `params` it declares. The outcomes come back in
+/// `PUZZLES` order.
+pub fn solve_all(dir: &Path, params: &Params) -> Vec<Outcome> {
+    let num_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let next = AtomicUsize::new(0);
+    let outcomes: Vec<Mutex<Option<Outcome>>> = PUZZLES.iter().map(|_| Mutex::new(None)).collect();
+
+    thread::scope(|scope| {
+        for _ in 0..num_threads.min(PUZZLES.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= PUZZLES.len() {
+                    break;
+                }
+                let puzzle = &PUZZLES[i];
+                let path = input_path(dir, puzzle.day);
+                let outcome = match fs::read_to_string(&path) {
+                    Err(err) => Outcome::Missing(format!("{}: {}", path.display(), err)),
+                    Ok(input) => match puzzle.solve_timed(&input, &params.only(puzzle.params)) {
+                        Ok(record) => Outcome::Solved(record),
+                        Err(err) => Outcome::Failed(err.to_string()),
+                    },
+                };
+                *outcomes[i].lock().unwrap() = Some(outcome);
+            });
+        }
+    });
+
+    outcomes
+        .into_iter()
+        .map(|o| {
+            o.into_inner()
+                .unwrap()
+                .expect("every puzzle is solved once")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_all_without_inputs() {
+        let outcomes = solve_all(Path::new("no/such/inputs"), &Params::default());
+        assert_eq!(outcomes.len(), PUZZLES.len());
+        assert!(outcomes.iter().all(|o| matches!(o, Outcome::Missing(_))));
+    }
+}
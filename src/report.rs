@@ -0,0 +1,102 @@
+use crate::solution::Answer;
+use std::fmt::Write;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of solving one part on one input.
+pub struct Record {
+    pub day: u32,
+    pub part: u32,
+    pub answer: Answer,
+    pub elapsed: Duration,
+    pub input_digest: u64,
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64-bit FNV-1a, enough to tell inputs apart without pulling in a hashing crate.
+pub fn digest(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+impl Record {
+    pub fn to_json(&self) -> String {
+        let answer = match &self.answer {
+            Answer::Number(n) => n.to_string(),
+            Answer::Unsigned(n) => n.to_string(),
+            Answer::Text(s) => json_string(s),
+        };
+        format!(
+            "{{\"day\":{},\"part\":{},\"answer\":{},\"elapsed_ns\":{},\"input_digest\":\"fnv1a64:{:016x}\"}}",
+            self.day,
+            self.part,
+            answer,
+            self.elapsed.as_nanos(),
+            self.input_digest
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest() {
+        assert_eq!(digest(b""), FNV_OFFSET_BASIS);
+        assert_eq!(digest(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let record = Record {
+            day: 10,
+            part: 2,
+            answer: Answer::from("#.\n\"."),
+            elapsed: Duration::from_micros(3),
+            input_digest: 0xff,
+        };
+        assert_eq!(
+            record.to_json(),
+            "{\"day\":10,\"part\":2,\"answer\":\"#.\\n\\\".\",\"elapsed_ns\":3000,\"input_digest\":\"fnv1a64:00000000000000ff\"}"
+        );
+    }
+}
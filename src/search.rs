@@ -0,0 +1,219 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::iter;
+use std::ops;
+use std::vec::Vec;
+
+// every state seen so far, with the index of the state it was reached from.
+struct Visited<S, C> {
+    index: HashMap<S, usize>,
+    nodes: Vec<(S, Option<usize>, C)>,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> Visited<S, C> {
+    fn new() -> Self {
+        Self {
+            index: HashMap::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, state: S, parent: Option<usize>, cost: C) -> usize {
+        let idx = self.nodes.len();
+        self.index.insert(state.clone(), idx);
+        self.nodes.push((state, parent, cost));
+        idx
+    }
+
+    fn path(&self, mut idx: usize) -> Vec<S> {
+        let mut path = vec![self.nodes[idx].0.clone()];
+        while let Some(parent) = self.nodes[idx].1 {
+            path.push(self.nodes[parent].0.clone());
+            idx = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Breadth-first search from `start` to the first state satisfying `is_goal`.
+///
+/// Returns the number of steps together with the path, both ends included.
+pub fn bfs<S, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<(usize, Vec<S>)>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    bfs_multi(iter::once(start), successors, is_goal)
+}
+
+/// Breadth-first search from all of `starts` at once; the path begins at
+/// whichever start is nearest to a goal.
+pub fn bfs_multi<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<(usize, Vec<S>)>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut visited = Visited::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if !visited.index.contains_key(&start) {
+            queue.push_back(visited.push(start, None, 0));
+        }
+    }
+
+    while let Some(idx) = queue.pop_front() {
+        let (cur, _, steps) = visited.nodes[idx].clone();
+        if is_goal(&cur) {
+            return Some((steps, visited.path(idx)));
+        }
+        for next in successors(&cur) {
+            if !visited.index.contains_key(&next) {
+                queue.push_back(visited.push(next, Some(idx), steps + 1));
+            }
+        }
+    }
+    None
+}
+
+/// The number of steps from `start` to every state reachable from it.
+pub fn bfs_distances<S, I>(start: S, mut successors: impl FnMut(&S) -> I) -> HashMap<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut distances = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((cur, steps)) = queue.pop_front() {
+        for next in successors(&cur) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), steps + 1);
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Cheapest path from `start` to a goal, `successors` yielding each next state
+/// with the (non-negative) cost of the step.
+pub fn dijkstra<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<(C, Vec<S>)>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + ops::Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(start, successors, |_| C::default(), is_goal)
+}
+
+/// Like `dijkstra`, but states are explored in order of cost so far plus
+/// `heuristic`, which must never overestimate the remaining cost.
+pub fn astar<S, C, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<(C, Vec<S>)>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + ops::Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut visited = Visited::new();
+    let mut heap = BinaryHeap::new();
+    let estimate = heuristic(&start);
+    heap.push(Reverse((
+        estimate,
+        C::default(),
+        visited.push(start, None, C::default()),
+    )));
+
+    while let Some(Reverse((_, cost, idx))) = heap.pop() {
+        let (cur, _, best) = visited.nodes[idx].clone();
+        // a cheaper way here has been found since this entry was queued.
+        if cost > best {
+            continue;
+        }
+        if is_goal(&cur) {
+            return Some((cost, visited.path(idx)));
+        }
+        for (next, step) in successors(&cur) {
+            let next_cost = cost + step;
+            let next_idx = match visited.index.get(&next) {
+                Some(&seen) if visited.nodes[seen].2 <= next_cost => continue,
+                Some(&seen) => {
+                    visited.nodes[seen].1 = Some(idx);
+                    visited.nodes[seen].2 = next_cost;
+                    seen
+                }
+                None => visited.push(next, Some(idx), next_cost),
+            };
+            let estimate = heuristic(&visited.nodes[next_idx].0);
+            heap.push(Reverse((next_cost + estimate, next_cost, next_idx)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 -> 1 -> 3, 0 -> 2 -> 3 with the second way cheaper by weight.
+    fn edges(node: &usize) -> Vec<(usize, u32)> {
+        match node {
+            0 => vec![(1, 1), (2, 4)],
+            1 => vec![(3, 10)],
+            2 => vec![(3, 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_bfs() {
+        let next = |n: &usize| edges(n).into_iter().map(|(m, _)| m);
+        assert_eq!(bfs(0, next, |n| *n == 3), Some((2, vec![0, 1, 3])));
+        assert_eq!(bfs(0, next, |n| *n == 0), Some((0, vec![0])));
+        assert_eq!(bfs(3, next, |n| *n == 0), None);
+        assert_eq!(bfs_multi([0, 2], next, |n| *n == 3), Some((1, vec![2, 3])));
+        assert_eq!(
+            bfs_distances(0, next),
+            HashMap::from([(0, 0), (1, 1), (2, 1), (3, 2)])
+        );
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        assert_eq!(dijkstra(0, edges, |n| *n == 3), Some((5, vec![0, 2, 3])));
+        assert_eq!(dijkstra(1, edges, |n| *n == 2), None);
+    }
+
+    #[test]
+    fn test_astar() {
+        // walk on an open grid; manhattan distance is an exact heuristic.
+        let goal = (3_i32, -2_i32);
+        let next =
+            |&(x, y): &(i32, i32)| [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)].map(|p| (p, 1));
+        let heuristic = |&(x, y): &(i32, i32)| (goal.0 - x).abs() + (goal.1 - y).abs();
+        let (cost, path) = astar((0, 0), next, heuristic, |p| *p == goal).unwrap();
+        assert_eq!(cost, 5);
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&goal));
+    }
+}
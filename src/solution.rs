@@ -0,0 +1,109 @@
+use crate::error::Result;
+use crate::params::Params;
+use std::fmt;
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    /// An unsigned number too large for `Number`; smaller ones are always `Number`.
+    Unsigned(u64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+macro_rules! answer_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Number(i64::from(n))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! answer_from_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    match i64::try_from(n) {
+                        Ok(n) => Answer::Number(n),
+                        Err(_) => Answer::Unsigned(n as u64),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+answer_from_number!(i32, i64, u32);
+answer_from_unsigned!(u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+/// A day's puzzle: the input is parsed once into a `Model` which both parts
+/// then solve without touching the input again.
+pub trait Solution {
+    type Model;
+
+    /// Keys the parts read from `Params`, each falling back to the real puzzle's value.
+    const PARAMS: &'static [&'static str] = &[];
+
+    fn parse(input: impl BufRead) -> Result<Self::Model>;
+    fn part1(model: &Self::Model, params: &Params) -> Result<Answer>;
+    fn part2(model: &Self::Model, params: &Params) -> Result<Answer>;
+
+    fn parse_str(input: &str) -> Result<Self::Model> {
+        Self::parse(input.as_bytes())
+    }
+}
+
+/// Lines of `input` paired with their 1-based line number.
+pub fn numbered_lines(input: impl BufRead) -> impl Iterator<Item = Result<(usize, String)>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Ok((i + 1, line?)))
+}
+
+pub fn solve_part1<S: Solution>(input: &mut dyn BufRead, params: &Params) -> Result<Answer> {
+    S::part1(&S::parse(input)?, params)
+}
+
+pub fn solve_part2<S: Solution>(input: &mut dyn BufRead, params: &Params) -> Result<Answer> {
+    S::part2(&S::parse(input)?, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answer_from_large_unsigned() {
+        assert_eq!(Answer::from(42_usize), Answer::Number(42));
+        assert_eq!(Answer::from(u64::MAX), Answer::Unsigned(u64::MAX));
+        assert_eq!(Answer::from(u64::MAX).to_string(), "18446744073709551615");
+    }
+}